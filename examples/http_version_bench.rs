@@ -0,0 +1,48 @@
+// HTTP/1.1 与 HTTP/2 下载速度对比
+//
+// 用法: cargo run --release --example http_version_bench -- [MODEL_ID]
+use async_trait::async_trait;
+use modelscope_ng::{DownloadOptions, ModelScope, ProgressCallback};
+use std::time::Instant;
+
+/// 不输出任何内容的回调，避免打印影响计时
+#[derive(Clone)]
+struct Quiet;
+
+#[async_trait]
+impl ProgressCallback for Quiet {
+    async fn on_file_start(&self, _file_name: &str, _file_size: u64) {}
+
+    async fn on_file_progress(&self, _file_name: &str, _downloaded: u64, _total: u64) {}
+
+    async fn on_file_complete(&self, _file_name: &str) {}
+
+    async fn on_file_error(&self, file_name: &str, error: &str) {
+        eprintln!("[错误] {} - {}", file_name, error);
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let model_id = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "damo/nlp_structbert_backbone_base_std".to_string());
+
+    for (label, http1_only) in [("HTTP/1.1", true), ("HTTP/2", false)] {
+        // 每轮使用新的目录，保证不会命中断点续传
+        let save_dir = std::env::temp_dir().join(format!("modelscope-bench-{}", http1_only));
+        let _ = std::fs::remove_dir_all(&save_dir);
+
+        let options = DownloadOptions {
+            http1_only,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        ModelScope::download_with_options(&model_id, &save_dir, options, Quiet).await?;
+        println!("{:<10} {:.2?}", label, start.elapsed());
+
+        std::fs::remove_dir_all(&save_dir)?;
+    }
+
+    Ok(())
+}
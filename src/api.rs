@@ -0,0 +1,59 @@
+use crate::{DownloadOptions, ModelScopeError, throttle};
+use anyhow::Context;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+/// The envelope every hub API answers with.
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    #[serde(rename = "Code")]
+    code: i64,
+    #[serde(rename = "Success")]
+    success: bool,
+    #[serde(rename = "Message", default)]
+    message: String,
+    #[serde(rename = "Data")]
+    data: Option<T>,
+}
+
+/// A task tag of a model, like `text-generation`
+#[derive(Deserialize)]
+pub(crate) struct Task {
+    #[serde(rename = "Name")]
+    pub(crate) name: String,
+}
+
+/// Send a hub API request and return its `Data`, HTTP and API failures are reported as a
+/// [`ModelScopeError`] with "Failed to `action`" as context.
+pub(crate) async fn send<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    options: &DownloadOptions,
+    action: &str,
+) -> anyhow::Result<Option<T>> {
+    throttle::throttle_api(options.api_min_interval).await;
+    let context = || format!("Failed to {}", action);
+    tracing::debug!(action, "API request");
+    let resp = request.send().await.with_context(context)?;
+
+    let status = resp.status();
+    tracing::debug!(action, %status, "API response");
+    if !status.is_success() {
+        return Err(
+            anyhow::Error::new(ModelScopeError::from_status(status)).context(format!(
+                "Failed to {}: HTTP {} {}",
+                action,
+                status,
+                resp.text().await?
+            )),
+        );
+    }
+    let response = resp.json::<ApiResponse<T>>().await.with_context(context)?;
+    if !response.success {
+        let error = ModelScopeError::Api {
+            code: response.code,
+            message: response.message,
+        };
+        return Err(anyhow::Error::new(error).context(context()));
+    }
+    Ok(response.data)
+}
@@ -0,0 +1,12 @@
+use crate::RepoFile;
+use async_trait::async_trait;
+
+/// Decides per file whether it is downloaded, e.g. to ask before large files or to enforce a
+/// policy like no files over 10 GB.
+///
+/// Files are asked about one at a time, in listing order, before any of them is streamed, so an
+/// implementation may prompt interactively. Denied files are reported as skipped.
+#[async_trait]
+pub trait ApproveFile: Send + Sync {
+    async fn should_download(&self, file: &RepoFile) -> bool;
+}
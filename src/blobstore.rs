@@ -0,0 +1,65 @@
+use crate::RepoFile;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Computes where a file lives in a [`BlobStore`], relative to its root.
+/// An empty key means the file is not stored.
+pub type BlobKeyFn = Arc<dyn Fn(&RepoFile) -> String + Send + Sync>;
+
+/// A content store shared by models, so identical files are only downloaded once.
+///
+/// Files are downloaded into the store and hard linked into the model directory,
+/// or copied when the store is on another filesystem.
+#[derive(Clone)]
+pub struct BlobStore {
+    pub root: PathBuf,
+    pub key: BlobKeyFn,
+}
+
+impl BlobStore {
+    /// A store keyed by [`sha256_key`].
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            key: Arc::new(sha256_key),
+        }
+    }
+
+    /// Use a custom key, to share an existing cache with a different layout.
+    pub fn with_key(mut self, key: impl Fn(&RepoFile) -> String + Send + Sync + 'static) -> Self {
+        self.key = Arc::new(key);
+        self
+    }
+
+    pub(crate) fn blob_path(&self, file: &RepoFile) -> Option<PathBuf> {
+        let key = (self.key)(file);
+        if key.is_empty() {
+            return None;
+        }
+        Some(self.root.join(key))
+    }
+
+    /// Make `dest` a copy of the blob.
+    pub(crate) fn link(blob: &Path, dest: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if dest.exists() {
+            fs::remove_file(dest)?;
+        }
+        if let Err(e) = fs::hard_link(blob, dest) {
+            tracing::debug!(blob = %blob.display(), error = %e, "hard link failed, copying");
+            fs::copy(blob, dest)?;
+        }
+        Ok(())
+    }
+}
+
+/// `blobs/<sha256>`, the default key. Files without a sha256 are not stored.
+pub fn sha256_key(file: &RepoFile) -> String {
+    if file.sha256.is_empty() {
+        return String::new();
+    }
+    format!("blobs/{}", file.sha256.to_lowercase())
+}
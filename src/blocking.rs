@@ -0,0 +1,92 @@
+//! A blocking API for programs without an async runtime, like [`reqwest::blocking`].
+//!
+//! The functions run the async ones on a runtime of their own, started on first use and shared
+//! by every call. They can't be called from async code, use [`ModelScope`] there.
+
+use crate::{DownloadOptions, DownloadSummary, ModelScope, ProgressCallback, RepoFile};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+fn block_on<F: Future>(future: F) -> anyhow::Result<F::Output> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+        anyhow::bail!("The blocking API can't be used in an async runtime, use ModelScope instead");
+    }
+    let runtime = match RUNTIME.get() {
+        Some(runtime) => runtime,
+        None => {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .thread_name("modelscope-ng")
+                .enable_all()
+                .build()?;
+            RUNTIME.get_or_init(|| runtime)
+        }
+    };
+    Ok(runtime.block_on(future))
+}
+
+/// Blocking [`ModelScope::download`].
+pub fn download(model_id: &str, save_dir: impl Into<PathBuf>) -> anyhow::Result<()> {
+    block_on(ModelScope::download(model_id, save_dir))?
+}
+
+/// Blocking [`ModelScope::download_with_options`].
+pub fn download_with_options<C: ProgressCallback + Clone + 'static>(
+    model_id: &str,
+    save_dir: impl Into<PathBuf>,
+    options: DownloadOptions,
+    callback: C,
+) -> anyhow::Result<DownloadSummary> {
+    block_on(ModelScope::download_with_options(
+        model_id, save_dir, options, callback,
+    ))?
+}
+
+/// Blocking [`ModelScope::download_single_file`].
+pub fn download_single_file(
+    model_id: &str,
+    file_path: &str,
+    save_dir: impl Into<PathBuf>,
+) -> anyhow::Result<()> {
+    block_on(ModelScope::download_single_file(
+        model_id, file_path, save_dir,
+    ))?
+}
+
+/// Blocking [`ModelScope::download_single_file_with_options`].
+pub fn download_single_file_with_options<C: ProgressCallback + Clone + 'static>(
+    model_id: &str,
+    file_path: &str,
+    save_dir: impl Into<PathBuf>,
+    options: DownloadOptions,
+    callback: C,
+) -> anyhow::Result<DownloadSummary> {
+    block_on(ModelScope::download_single_file_with_options(
+        model_id, file_path, save_dir, options, callback,
+    ))?
+}
+
+/// Blocking [`ModelScope::list_files`].
+pub fn list_files(model_id: &str) -> anyhow::Result<Vec<RepoFile>> {
+    block_on(ModelScope::list_files(model_id))?
+}
+
+/// Blocking [`ModelScope::list_files_with_options`].
+pub fn list_files_with_options(
+    model_id: &str,
+    options: &DownloadOptions,
+) -> anyhow::Result<Vec<RepoFile>> {
+    block_on(ModelScope::list_files_with_options(model_id, options))?
+}
+
+/// Blocking [`ModelScope::login`].
+pub fn login(token: &str) -> anyhow::Result<()> {
+    block_on(ModelScope::login(token))?
+}
+
+/// Blocking [`ModelScope::login_with_options`].
+pub fn login_with_options(token: &str, options: &DownloadOptions) -> anyhow::Result<()> {
+    block_on(ModelScope::login_with_options(token, options))?
+}
@@ -0,0 +1,193 @@
+use crate::{Dirs, DownloadOptions};
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "config.toml";
+/// One save dir per line, how older versions kept them. Moved into the config on the next save.
+const LEGACY_KNOWN_SAVE_DIRS: &str = "known_save_dirs";
+
+/// User defaults from `~/.modelscope/config/config.toml`, used by the CLI and by programs that
+/// [`apply`](Self::apply) them. Options set in code or on the command line take precedence.
+///
+/// ```toml
+/// save_dir = "/data/models"
+/// jobs = 8
+/// endpoint = "https://modelscope.cn"
+/// proxy = "socks5h://127.0.0.1:1080"
+/// include = ["*.safetensors", "*.json"]
+/// exclude = ["original/*"]
+/// api_interval = "500ms"
+/// ```
+///
+/// The file also has `known_save_dirs`, every directory models were downloaded to, which the
+/// downloads keep up to date.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Where models are saved when no directory is given
+    pub save_dir: Option<PathBuf>,
+    /// Files downloaded at once, see [`DownloadOptions::max_open_files`]
+    pub jobs: Option<usize>,
+    /// Hub endpoint, [`ENDPOINT_ENV`](crate::ENDPOINT_ENV) still takes precedence
+    pub endpoint: Option<String>,
+    pub proxy: Option<String>,
+    /// Glob patterns of the files to download, see [`DownloadOptions::include`]
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Minimum time between hub API requests, like `500ms`
+    #[serde(deserialize_with = "duration")]
+    pub api_interval: Option<Duration>,
+    known_save_dirs: Vec<PathBuf>,
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let Some(s) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    humantime::parse_duration(&s)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+impl Config {
+    /// `~/.modelscope/config/config.toml`, which may not exist.
+    pub fn path() -> anyhow::Result<PathBuf> {
+        Ok(Dirs::config_dir()?.join(CONFIG_FILE))
+    }
+
+    /// Load the config file, the defaults when there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        Self::load_in(&Dirs::config_dir()?)
+    }
+
+    fn load_in(config_dir: &Path) -> anyhow::Result<Self> {
+        let f = config_dir.join(CONFIG_FILE);
+        if !f.exists() {
+            return Ok(Self::default());
+        }
+        toml_edit::de::from_str(&fs::read_to_string(&f)?)
+            .with_context(|| format!("Failed to parse {}", f.display()))
+    }
+
+    /// The options with the defaults of the config for everything they don't set.
+    pub fn apply(&self, options: DownloadOptions) -> DownloadOptions {
+        let endpoint = match options.endpoint {
+            None if crate::env_endpoint().is_none() => self.endpoint.clone(),
+            endpoint => endpoint,
+        };
+        let include = if options.include.is_empty() {
+            self.include.clone()
+        } else {
+            options.include
+        };
+        let exclude = if options.exclude.is_empty() {
+            self.exclude.clone()
+        } else {
+            options.exclude
+        };
+        DownloadOptions {
+            endpoint,
+            proxy: options.proxy.or_else(|| self.proxy.clone()),
+            max_open_files: options.max_open_files.or(self.jobs),
+            include,
+            exclude,
+            api_min_interval: options.api_min_interval.or(self.api_interval),
+            ..options
+        }
+    }
+
+    /// Remember a directory models were downloaded to, for listing them later.
+    pub(crate) fn append_save_dir(dir: &Path) -> anyhow::Result<()> {
+        Self::append_save_dir_in(&Dirs::config_dir()?, dir)
+    }
+
+    fn append_save_dir_in(config_dir: &Path, dir: &Path) -> anyhow::Result<()> {
+        let dir = dir.canonicalize()?;
+        let mut known_save_dirs = Self::known_save_dirs_in(config_dir)?;
+        let legacy = config_dir.join(LEGACY_KNOWN_SAVE_DIRS);
+        if known_save_dirs.contains(&dir) && !legacy.exists() {
+            return Ok(());
+        }
+        if !known_save_dirs.contains(&dir) {
+            known_save_dirs.push(dir);
+        }
+
+        // Edited in place, so the comments and layout of the user's settings are kept
+        let f = config_dir.join(CONFIG_FILE);
+        let mut doc = match fs::read_to_string(&f) {
+            Ok(s) => s
+                .parse::<toml_edit::DocumentMut>()
+                .with_context(|| format!("Failed to parse {}", f.display()))?,
+            Err(_) => toml_edit::DocumentMut::new(),
+        };
+        let dirs: toml_edit::Array = known_save_dirs
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        doc["known_save_dirs"] = toml_edit::value(dirs);
+        fs::write(&f, doc.to_string())?;
+
+        if legacy.exists() {
+            fs::remove_file(legacy)?;
+        }
+        Ok(())
+    }
+
+    /// The directories models were downloaded to that still exist.
+    pub(crate) fn get_known_save_dirs() -> anyhow::Result<Vec<PathBuf>> {
+        Self::known_save_dirs_in(&Dirs::config_dir()?)
+    }
+
+    fn known_save_dirs_in(config_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let mut candidates = Self::load_in(config_dir)?.known_save_dirs;
+        let legacy = config_dir.join(LEGACY_KNOWN_SAVE_DIRS);
+        if legacy.exists() {
+            candidates.extend(fs::read_to_string(legacy)?.lines().map(PathBuf::from));
+        }
+
+        let mut paths: Vec<PathBuf> = vec![];
+        for path in candidates {
+            // Non-existent paths are skipped, they are dropped when append_save_dir saves the
+            // list. Older versions may have stored paths through a symlink, resolve them so
+            // the same dir is not listed twice.
+            let Ok(path) = path.canonicalize() else {
+                continue;
+            };
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ModelScope;
+
+    #[cfg(unix)]
+    #[test]
+    fn models_under_a_symlinked_save_dir_are_listed() {
+        let root = tempfile::tempdir().unwrap();
+        let config_dir = root.path().join("config");
+        let target = root.path().join("target");
+        let link = root.path().join("link");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::create_dir_all(target.join("Qwen/Qwen3-0.6B")).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        // Registered through the link and directly, the same dir is only known once
+        Config::append_save_dir_in(&config_dir, &link).unwrap();
+        Config::append_save_dir_in(&config_dir, &target).unwrap();
+        let known = Config::known_save_dirs_in(&config_dir).unwrap();
+        assert_eq!(known, vec![target.canonicalize().unwrap()]);
+
+        let models = ModelScope::list_in(known).unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].0, "Qwen/Qwen3-0.6B");
+    }
+}
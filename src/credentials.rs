@@ -0,0 +1,73 @@
+use crate::{COOKIES_FILE, Dirs, profile};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Service name of the login in the OS keyring
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "modelscope-ng";
+
+/// Keep the login cookies of a profile, or of the default login when `None`: in the OS keyring
+/// with the `keyring` feature, and in a file in the config dir otherwise or when no keyring is
+/// available.
+pub(crate) fn save(profile: Option<&str>, cookies: &str) -> anyhow::Result<()> {
+    #[cfg(feature = "keyring")]
+    match keyring_entry(profile).and_then(|entry| Ok(entry.set_password(cookies)?)) {
+        // A plaintext copy of an earlier login is not needed anymore
+        Ok(()) => return remove_file(profile),
+        Err(e) => tracing::warn!(
+            error = %e,
+            "failed to store the login in the keyring, saving it to a file"
+        ),
+    }
+    fs::write(cookies_file(profile)?, cookies)?;
+    Ok(())
+}
+
+/// The saved login cookies as JSON, `None` when not logged in.
+pub(crate) fn load(profile: Option<&str>) -> anyhow::Result<Option<String>> {
+    #[cfg(feature = "keyring")]
+    if let Ok(cookies) = keyring_entry(profile).and_then(|entry| Ok(entry.get_password()?)) {
+        return Ok(Some(cookies));
+    }
+    match fs::read_to_string(cookies_file(profile)?) {
+        Ok(cookies) => Ok(Some(cookies)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub(crate) fn delete(profile: Option<&str>) -> anyhow::Result<()> {
+    #[cfg(feature = "keyring")]
+    if let Ok(entry) = keyring_entry(profile) {
+        let _ = entry.delete_credential();
+    }
+    remove_file(profile)
+}
+
+/// One entry per config dir and profile, so a `MODELSCOPE_HOME` keeps its own logins.
+#[cfg(feature = "keyring")]
+fn keyring_entry(profile: Option<&str>) -> anyhow::Result<keyring::Entry> {
+    let mut user = Dirs::config_dir()?.display().to_string();
+    if let Some(profile) = profile {
+        user = format!("{}#{}", user, profile);
+    }
+    Ok(keyring::Entry::new(KEYRING_SERVICE, &user)?)
+}
+
+fn cookies_file(profile: Option<&str>) -> anyhow::Result<PathBuf> {
+    match profile {
+        Some(profile) => {
+            profile::check_name(profile)?;
+            Ok(profile::profiles_dir()?.join(format!("{}.{}", profile, COOKIES_FILE)))
+        }
+        None => Ok(Dirs::config_dir()?.join(COOKIES_FILE)),
+    }
+}
+
+fn remove_file(profile: Option<&str>) -> anyhow::Result<()> {
+    match fs::remove_file(cookies_file(profile)?) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,76 @@
+use crate::Dirs;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+const DNS_CACHE_FILE: &str = "dns_cache.json";
+
+/// Host name to the addresses it last resolved to, shared by every client and kept on disk so a
+/// flaky resolver on the next run can fall back to them too
+static CACHE: OnceLock<Mutex<HashMap<String, Vec<IpAddr>>>> = OnceLock::new();
+
+/// Resolves with the system resolver and falls back to the last known addresses of the host
+/// when resolution fails.
+pub(crate) struct CachingResolver;
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            match tokio::net::lookup_host((host.as_str(), 0)).await {
+                Ok(addrs) => {
+                    let addrs: Vec<SocketAddr> = addrs.collect();
+                    tracing::trace!(host, ?addrs, "resolved");
+                    remember(&host, addrs.iter().map(SocketAddr::ip).collect());
+                    Ok(Box::new(addrs.into_iter()) as Addrs)
+                }
+                Err(e) => {
+                    let Some(ips) = cache().lock().unwrap().get(&host).cloned() else {
+                        return Err(e.into());
+                    };
+                    tracing::warn!(
+                        host,
+                        error = %e,
+                        "failed to resolve, using the last known addresses"
+                    );
+                    let addrs = ips.into_iter().map(|ip| SocketAddr::new(ip, 0));
+                    Ok(Box::new(addrs.collect::<Vec<_>>().into_iter()) as Addrs)
+                }
+            }
+        })
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<String, Vec<IpAddr>>> {
+    CACHE.get_or_init(|| {
+        let cached = cache_file()
+            .and_then(|f| Ok(serde_json::from_str(&fs::read_to_string(f)?)?))
+            .unwrap_or_default();
+        Mutex::new(cached)
+    })
+}
+
+fn remember(host: &str, mut ips: Vec<IpAddr>) {
+    if ips.is_empty() {
+        return;
+    }
+    ips.sort();
+    let mut cache = cache().lock().unwrap();
+    if cache.get(host) == Some(&ips) {
+        return;
+    }
+    cache.insert(host.to_string(), ips);
+    // Only a fallback, failing to save it must not fail the request
+    if let Ok(f) = cache_file()
+        && let Ok(json) = serde_json::to_string_pretty(&*cache)
+    {
+        let _ = fs::write(f, json);
+    }
+}
+
+fn cache_file() -> anyhow::Result<PathBuf> {
+    Ok(Dirs::config_dir()?.join(DNS_CACHE_FILE))
+}
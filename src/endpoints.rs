@@ -0,0 +1,25 @@
+use std::time::{Duration, Instant};
+
+/// How long a hub endpoint may take to answer the probe before it counts as unreachable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Order hub endpoints by the latency of a HEAD request, fastest first. Endpoints that did not
+/// answer are kept at the end in their original order, as a last resort.
+pub(crate) async fn rank_endpoints(client: &reqwest::Client, endpoints: &[String]) -> Vec<String> {
+    let probes = endpoints.iter().map(|endpoint| async move {
+        let start = Instant::now();
+        let res = client
+            .head(endpoint.as_str())
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await;
+        (endpoint, res.ok().map(|_| start.elapsed()))
+    });
+    let mut probed = futures_util::future::join_all(probes).await;
+    // Stable, so unreachable endpoints keep their order
+    probed.sort_by_key(|(_, latency)| latency.unwrap_or(Duration::MAX));
+    probed
+        .into_iter()
+        .map(|(endpoint, _)| endpoint.trim_end_matches('/').to_string())
+        .collect()
+}
@@ -0,0 +1,139 @@
+use crate::ChecksumMismatch;
+use reqwest::StatusCode;
+
+/// What went wrong, for callers that need to react differently to each failure.
+///
+/// Functions of this crate return [`anyhow::Error`]s with context for people to read, and keep
+/// doing so. [`ModelScopeError::classify`] is the supported way to get the typed cause: it finds
+/// it anywhere in the chain, including causes raised by reqwest or `std::io`. A plain
+/// `err.downcast_ref::<ModelScopeError>()` only sees the errors this crate raised as one.
+///
+/// ```no_run
+/// # async fn run() -> anyhow::Result<()> {
+/// use modelscope_ng::{ModelScope, ModelScopeError};
+///
+/// if let Err(e) = ModelScope::download("Qwen/Qwen3-0.6B", "./models").await {
+///     match ModelScopeError::classify(&e) {
+///         Some(ModelScopeError::Auth { .. }) => eprintln!("Run login first"),
+///         Some(ModelScopeError::NotFound { .. }) => eprintln!("No such model"),
+///         _ => return Err(e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, thiserror::Error)]
+#[non_exhaustive]
+pub enum ModelScopeError {
+    /// Login is required, or the saved cookies or token were rejected (HTTP 401 or 403)
+    #[error("authentication failed: HTTP {status}")]
+    Auth { status: StatusCode },
+    /// The repository, revision or file does not exist, or is private (HTTP 404)
+    #[error("not found: HTTP {status}")]
+    NotFound { status: StatusCode },
+    /// Any other unexpected HTTP status
+    #[error("HTTP {status}")]
+    Http { status: StatusCode },
+    /// The API answered but reported a failure
+    #[error("API error {code}: {message}")]
+    Api { code: i64, message: String },
+    /// Reading or writing local files failed, like a full disk. Network failures never are.
+    #[error("{message}")]
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// Downloaded content does not match its expected hash
+    #[error(transparent)]
+    Checksum(ChecksumMismatch),
+    /// The download was stopped through
+    /// [`DownloadOptions::cancel`](crate::DownloadOptions::cancel)
+    #[error("download cancelled")]
+    Cancelled,
+}
+
+impl ModelScopeError {
+    pub(crate) fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Auth { status },
+            StatusCode::NOT_FOUND => Self::NotFound { status },
+            _ => Self::Http { status },
+        }
+    }
+
+    /// The HTTP status behind the error, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Auth { status } | Self::NotFound { status } | Self::Http { status } => {
+                Some(*status)
+            }
+            _ => None,
+        }
+    }
+
+    /// The typed cause of an error returned by this crate, the first one found from the
+    /// outermost context in. `None` for failures like a bad argument or a dropped connection.
+    ///
+    /// Works for the errors of every public function, sync or async.
+    pub fn classify(err: &anyhow::Error) -> Option<Self> {
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<Self>() {
+                return Some(e.clone());
+            }
+            if let Some(e) = cause.downcast_ref::<ChecksumMismatch>() {
+                return Some(Self::Checksum(e.clone()));
+            }
+            // A network failure carries the io error of the socket, which is not a local file
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return e.status().map(Self::from_status);
+            }
+            if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+                return Some(Self::Io {
+                    kind: e.kind(),
+                    message: e.to_string(),
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn classify_finds_the_cause_behind_context() {
+        let err = anyhow::Error::new(ModelScopeError::from_status(StatusCode::NOT_FOUND))
+            .context("Failed to get files of model a/b");
+        assert!(matches!(
+            ModelScopeError::classify(&err),
+            Some(ModelScopeError::NotFound { .. })
+        ));
+
+        let io = std::io::Error::new(std::io::ErrorKind::StorageFull, "no space left");
+        let err = Err::<(), _>(io).context("Failed to write").unwrap_err();
+        assert!(matches!(
+            ModelScopeError::classify(&err),
+            Some(ModelScopeError::Io {
+                kind: std::io::ErrorKind::StorageFull,
+                ..
+            })
+        ));
+
+        assert!(ModelScopeError::classify(&anyhow::anyhow!("bad argument")).is_none());
+    }
+
+    #[tokio::test]
+    async fn refused_connection_is_not_a_local_io_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let err = reqwest::get(url).await.unwrap_err();
+        assert!(err.is_connect());
+        let err = anyhow::Error::new(err).context("Failed to get files of model a/b");
+        assert!(ModelScopeError::classify(&err).is_none());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{CLIENT, DownloadOptions, DownloadSummary, ModelScope, ProgressCallback, RunHooks};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// Events waiting to be received before progress events are dropped
+const EVENT_BUFFER: usize = 1024;
+
+/// Something that happened during [`ModelScope::download_with_events`], named by the repository
+/// path of the file.
+#[derive(Debug)]
+pub enum DownloadEvent {
+    Start {
+        file: String,
+        size: u64,
+    },
+    /// Dropped instead of waiting when the receiver falls behind, the next one has the latest
+    /// count anyway
+    Progress {
+        file: String,
+        downloaded: u64,
+        total: u64,
+    },
+    Complete {
+        file: String,
+    },
+    Error {
+        file: String,
+        error: String,
+    },
+    /// The file was not downloaded, e.g. because it is already complete
+    Skipped {
+        file: String,
+        reason: String,
+    },
+    /// The file is downloaded again after an error, `attempt` counts from 1
+    Retry {
+        file: String,
+        attempt: u32,
+        error: String,
+    },
+    /// The download is over, always the last event
+    Finished(anyhow::Result<DownloadSummary>),
+}
+
+/// Forwards the callbacks into the channel.
+#[derive(Clone)]
+struct EventCallback(mpsc::Sender<DownloadEvent>);
+
+#[async_trait]
+impl ProgressCallback for EventCallback {
+    async fn on_file_start(&self, file_name: &str, file_size: u64) {
+        let event = DownloadEvent::Start {
+            file: file_name.to_string(),
+            size: file_size,
+        };
+        let _ = self.0.send(event).await;
+    }
+
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
+        let event = DownloadEvent::Progress {
+            file: file_name.to_string(),
+            downloaded,
+            total,
+        };
+        let _ = self.0.try_send(event);
+    }
+
+    async fn on_file_complete(&self, file_name: &str) {
+        let event = DownloadEvent::Complete {
+            file: file_name.to_string(),
+        };
+        let _ = self.0.send(event).await;
+    }
+
+    async fn on_file_error(&self, file_name: &str, error: &str) {
+        let event = DownloadEvent::Error {
+            file: file_name.to_string(),
+            error: error.to_string(),
+        };
+        let _ = self.0.send(event).await;
+    }
+
+    async fn on_file_skipped(&self, file_name: &str, reason: &str) {
+        let event = DownloadEvent::Skipped {
+            file: file_name.to_string(),
+            reason: reason.to_string(),
+        };
+        let _ = self.0.send(event).await;
+    }
+
+    async fn on_file_retry(&self, file_name: &str, attempt: u32, error: &str) {
+        let event = DownloadEvent::Retry {
+            file: file_name.to_string(),
+            attempt,
+            error: error.to_string(),
+        };
+        let _ = self.0.send(event).await;
+    }
+}
+
+impl ModelScope {
+    /// Download a model and receive its progress as [`DownloadEvent`]s instead of through a
+    /// [`ProgressCallback`], e.g. to `select!` on them in a UI loop.
+    ///
+    /// The channel ends with [`DownloadEvent::Finished`]. Dropping the receiver doesn't stop the
+    /// download, cancel it with [`DownloadOptions::cancel`]. Must be called from within a Tokio
+    /// runtime.
+    pub fn download_with_events(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+    ) -> mpsc::Receiver<DownloadEvent> {
+        let (tx, rx) = mpsc::channel(EVENT_BUFFER);
+        let model_id = model_id.to_string();
+        let save_dir = save_dir.into();
+        // Task locals don't cross into spawned tasks
+        let client = CLIENT.try_with(Clone::clone).ok();
+        tokio::spawn(async move {
+            let callback = EventCallback(tx.clone());
+            let download = Self::download_and_notify(
+                &model_id,
+                save_dir,
+                options,
+                callback,
+                RunHooks::default(),
+            );
+            let result = match client {
+                Some(client) => CLIENT.scope(client, download).await,
+                None => download.await,
+            };
+            let _ = tx.send(DownloadEvent::Finished(result)).await;
+        });
+        rx
+    }
+}
@@ -0,0 +1,58 @@
+/// Descriptors kept free for the runtime, DNS, the manifest and the like
+const RESERVED_FDS: u64 = 64;
+/// Descriptors each connection of a download uses: the socket and a handle on the local file
+const FDS_PER_CONNECTION: u64 = 2;
+/// Used when the limit can't be read
+const FALLBACK_BUDGET: usize = 64;
+
+/// How many files can be downloaded at once, each over `connections`, without running out of
+/// file descriptors.
+pub(crate) fn download_budget(connections: usize) -> usize {
+    budget(open_files_limit(), connections)
+}
+
+fn budget(limit: Option<u64>, connections: usize) -> usize {
+    // One more for a mirror, which only single connection downloads have
+    let per_download = FDS_PER_CONNECTION * connections.max(1) as u64 + 1;
+    match limit {
+        Some(limit) => (limit.saturating_sub(RESERVED_FDS) / per_download).max(1) as usize,
+        None => FALLBACK_BUDGET,
+    }
+}
+
+#[cfg(unix)]
+fn open_files_limit() -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes to the struct we pass in
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        return None;
+    }
+    // rlim_t is not u64 on every platform
+    #[allow(clippy::unnecessary_cast)]
+    Some(limit.rlim_cur as u64)
+}
+
+#[cfg(not(unix))]
+fn open_files_limit() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_downloads_need_more_descriptors() {
+        assert_eq!(budget(Some(1024), 1), 320);
+        assert_eq!(budget(Some(1024), 8), 56);
+        // Always at least one file at a time
+        assert_eq!(budget(Some(16), 8), 1);
+        assert_eq!(budget(None, 8), FALLBACK_BUDGET);
+    }
+}
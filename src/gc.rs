@@ -0,0 +1,117 @@
+use crate::incomplete::{self, Sidecar, incomplete_path};
+use crate::{MANIFEST_FILE, Manifest};
+use anyhow::Context;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A download that was interrupted and left a partial file behind, see
+/// [`ModelScope::find_partial_downloads`].
+///
+/// [`ModelScope::find_partial_downloads`]: crate::ModelScope::find_partial_downloads
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialDownload {
+    /// Where the file is saved once complete
+    pub path: PathBuf,
+    /// Bytes of the partial file on disk
+    pub downloaded: u64,
+    /// Expected size, unknown when neither a sidecar nor a manifest records it
+    pub size: Option<u64>,
+    /// The model the file belongs to, when it is inside a directory with a manifest
+    pub model_id: Option<String>,
+    /// The directory with that manifest
+    pub model_dir: Option<PathBuf>,
+}
+
+impl PartialDownload {
+    /// The save dir to resume the model from, when the model is saved as
+    /// `<save_dir>/<model_id>`.
+    pub fn save_dir(&self) -> Option<PathBuf> {
+        let (model_id, model_dir) = (self.model_id.as_ref()?, self.model_dir.as_ref()?);
+        if !model_dir.ends_with(model_id) {
+            return None;
+        }
+        let depth = Path::new(model_id).components().count();
+        model_dir.ancestors().nth(depth).map(Path::to_path_buf)
+    }
+
+    /// Delete the partial file and its sidecar.
+    pub(crate) fn remove(&self) -> anyhow::Result<()> {
+        if incomplete_path(&self.path).exists() || Sidecar::read(&self.path).is_some() {
+            incomplete::discard_partial(&self.path)
+        } else {
+            // Written in place by older versions, the file itself is the partial download
+            fs::remove_file(&self.path)
+                .with_context(|| format!("Failed to delete {}", self.path.display()))
+        }
+    }
+}
+
+/// Collect the partial downloads under `dir`: `<file>.incomplete` files, sidecars left without
+/// their partial file, and files a manifest records as unfinished that are shorter than expected.
+pub(crate) fn scan(
+    dir: &Path,
+    model: Option<&(PathBuf, Manifest)>,
+    found: &mut Vec<PartialDownload>,
+) -> anyhow::Result<()> {
+    let own = if dir.join(MANIFEST_FILE).exists() {
+        Manifest::load(dir)?.map(|m| (dir.to_path_buf(), m))
+    } else {
+        None
+    };
+    if let Some((model_dir, manifest)) = &own {
+        for file in manifest.files.iter().filter(|f| !f.complete) {
+            let path = manifest.local_path(model_dir, &file.path);
+            if incomplete_path(&path).exists() {
+                continue;
+            }
+            if let Ok(metadata) = fs::metadata(&path)
+                && metadata.len() < file.size
+            {
+                found.push(partial(path, metadata.len(), Some(file.size), own.as_ref()));
+            }
+        }
+    }
+    let model = own.as_ref().or(model);
+
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Symlinks are not followed, linked blobs are scanned where they are stored
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if name != ".git" {
+                scan(&path, model, found)?;
+            }
+        } else if let Some(file_name) = name.strip_suffix(".incomplete") {
+            let file_path = path.with_file_name(file_name);
+            let size = Sidecar::read(&file_path).map(|s| s.size);
+            let downloaded = entry.metadata()?.len();
+            found.push(partial(file_path, downloaded, size, model));
+        } else if let Some(file_name) = name.strip_suffix(".incomplete.json") {
+            let file_path = path.with_file_name(file_name);
+            if !incomplete_path(&file_path).exists() {
+                let size = Sidecar::read(&file_path).map(|s| s.size);
+                found.push(partial(file_path, 0, size, model));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn partial(
+    path: PathBuf,
+    downloaded: u64,
+    size: Option<u64>,
+    model: Option<&(PathBuf, Manifest)>,
+) -> PartialDownload {
+    PartialDownload {
+        path,
+        downloaded,
+        size,
+        model_id: model.map(|(_, m)| m.model_id.clone()),
+        model_dir: model.map(|(dir, _)| dir.clone()),
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::Context;
+use regex::Regex;
+
+/// Include and exclude glob patterns of a download.
+///
+/// A file is kept when it matches any include pattern (or there are none) and no exclude
+/// pattern. Patterns without a `/` match the file name in any directory, like `*.safetensors`,
+/// patterns with a `/` match the whole repository path, like `onnx/*.onnx`.
+pub(crate) struct GlobFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl GlobFilter {
+    /// `None` when there is nothing to filter.
+    pub(crate) fn new(include: &[String], exclude: &[String]) -> anyhow::Result<Option<Self>> {
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(None);
+        }
+        let compile = |patterns: &[String]| -> anyhow::Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| to_regex(p).with_context(|| format!("Invalid glob pattern {:?}", p)))
+                .collect()
+        };
+        Ok(Some(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        }))
+    }
+
+    pub(crate) fn is_match(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|r| r.is_match(path)))
+            && !self.exclude.iter().any(|r| r.is_match(path))
+    }
+}
+
+/// `*` and `?` stay within a path component, `**` crosses directories and `[...]` is a
+/// character class.
+fn to_regex(pattern: &str) -> anyhow::Result<Regex> {
+    let pattern = pattern.trim_start_matches('/');
+    let mut re = String::from(if pattern.contains('/') { "^" } else { "(^|/)" });
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    // `**/` also matches no directory at all
+                    chars.next();
+                    re.push_str("(.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                re.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    re.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        re.push('\\');
+                    }
+                    re.push(c);
+                }
+                re.push(']');
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Ok(Regex::new(&re)?)
+}
@@ -0,0 +1,332 @@
+use crate::{
+    DEFAULT_REVISION, DownloadOptions, ModelScope, ModelScopeError, RepoFile, RepoType, redact_url,
+    throttle,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Base URL of the Hugging Face Hub, override with [`HF_ENDPOINT_ENV`]
+pub const HF_DEFAULT_ENDPOINT: &str = "https://huggingface.co";
+/// Base URL of the Hugging Face Hub when the backend doesn't set one, like a mirror
+pub const HF_ENDPOINT_ENV: &str = "HF_ENDPOINT";
+/// Access token for the Hugging Face Hub when the backend doesn't set one
+pub const HF_TOKEN_ENV: &str = "HF_TOKEN";
+
+/// Where repositories are listed and downloaded from, see [`DownloadOptions::hub`].
+///
+/// A backend only resolves listings and file URLs, downloading, resuming, verification and
+/// progress are shared by every hub.
+#[async_trait]
+pub trait HubBackend: Send + Sync {
+    /// Short name of the hub, like `modelscope` or `huggingface`
+    fn name(&self) -> &'static str;
+
+    /// Revision downloaded when the options don't name one.
+    fn default_revision(&self) -> &'static str;
+
+    /// Headers sent with every request, like the login. `token` is the one given to
+    /// [`ModelScopeClientBuilder::token`](crate::ModelScopeClientBuilder::token), if any.
+    fn default_headers(
+        &self,
+        options: &DownloadOptions,
+        token: Option<&str>,
+    ) -> anyhow::Result<HeaderMap>;
+
+    /// Every entry of a repository, or of `options.subdir` when the hub can list a subtree.
+    /// Filtering by subdir, depth and patterns is done by the caller.
+    async fn list_files(
+        &self,
+        client: &reqwest::Client,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>>;
+
+    /// URL the content of a repository file is downloaded from.
+    fn file_url(&self, model_id: &str, path: &str, options: &DownloadOptions) -> String;
+}
+
+/// Split a model ID like `hf://org/name` or `ms://org/name` into the hub its scheme selects and
+/// the plain ID. IDs without a scheme have no hub of their own.
+pub fn parse_hub_uri(uri: &str) -> (Option<Arc<dyn HubBackend>>, &str) {
+    if let Some(model_id) = uri.strip_prefix("hf://") {
+        return (Some(Arc::new(HuggingFaceHub::default())), model_id);
+    }
+    if let Some(model_id) = uri.strip_prefix("ms://") {
+        return (Some(Arc::new(ModelScopeHub)), model_id);
+    }
+    (None, uri)
+}
+
+/// ModelScope, the hub used when the options don't name one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModelScopeHub;
+
+#[async_trait]
+impl HubBackend for ModelScopeHub {
+    fn name(&self) -> &'static str {
+        "modelscope"
+    }
+
+    fn default_revision(&self) -> &'static str {
+        DEFAULT_REVISION
+    }
+
+    /// The cookies saved by `login`, and the token, else the one of the options or of
+    /// [`TOKEN_ENV`](crate::TOKEN_ENV).
+    fn default_headers(
+        &self,
+        options: &DownloadOptions,
+        token: Option<&str>,
+    ) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        if let Some(cookies) = ModelScope::get_cookies(options.profile.as_deref())? {
+            headers.insert("Cookie", cookies.parse()?);
+        }
+        let token = token
+            .map(str::to_string)
+            .or_else(|| options.token.clone())
+            .or_else(crate::env_token);
+        if let Some(token) = token {
+            headers.insert(AUTHORIZATION, bearer(&token)?);
+        }
+        Ok(headers)
+    }
+
+    async fn list_files(
+        &self,
+        client: &reqwest::Client,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        ModelScope::list_modelscope_files(client, model_id, options).await
+    }
+
+    fn file_url(&self, model_id: &str, path: &str, options: &DownloadOptions) -> String {
+        match options.repo_type {
+            RepoType::Model => crate::DOWNLOAD_URL
+                .replace("<endpoint>", options.endpoint())
+                .replace("<model_id>", model_id)
+                .replace("<revision>", options.revision())
+                .replace("<path>", path),
+            // The path is a query value here, so it has to be encoded
+            RepoType::Dataset => {
+                let url = crate::DATASET_DOWNLOAD_URL
+                    .replace("<endpoint>", options.endpoint())
+                    .replace("<model_id>", model_id);
+                let params = [
+                    ("Source", "SDK"),
+                    ("Revision", options.revision()),
+                    ("FilePath", path),
+                ];
+                reqwest::Url::parse_with_params(&url, params)
+                    .map(String::from)
+                    .unwrap_or(url)
+            }
+        }
+    }
+}
+
+/// The Hugging Face Hub, selected with `hf://` model IDs.
+///
+/// [`DownloadOptions::endpoint`], `token` and `profile` belong to ModelScope and are not sent
+/// here, the endpoint and token are set on the backend or read from [`HF_ENDPOINT_ENV`] and
+/// [`HF_TOKEN_ENV`]. Files stored without LFS have no sha256 in the listing and are not verified.
+#[derive(Debug, Clone, Default)]
+pub struct HuggingFaceHub {
+    endpoint: Option<String>,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HfEntry {
+    r#type: String,
+    path: String,
+    #[serde(default)]
+    size: u64,
+    lfs: Option<HfLfs>,
+}
+
+#[derive(Deserialize)]
+struct HfLfs {
+    /// sha256 of the content
+    oid: String,
+    size: u64,
+}
+
+impl HuggingFaceHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base URL of the hub, like a mirror, instead of [`HF_DEFAULT_ENDPOINT`].
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Access token for private and gated repositories.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    fn endpoint(&self) -> String {
+        self.endpoint
+            .clone()
+            .or_else(|| {
+                std::env::var(HF_ENDPOINT_ENV)
+                    .ok()
+                    .filter(|e| !e.is_empty())
+            })
+            .unwrap_or_else(|| HF_DEFAULT_ENDPOINT.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// `<endpoint>/<model_id>` for models, `<endpoint>/datasets/<model_id>` for datasets, with
+    /// `api` after the endpoint for the API.
+    fn repo_url(&self, model_id: &str, options: &DownloadOptions, api: bool) -> String {
+        let kind = match options.repo_type {
+            RepoType::Model if api => "models/",
+            RepoType::Model => "",
+            RepoType::Dataset => "datasets/",
+        };
+        let api = if api { "/api" } else { "" };
+        format!("{}{}/{}{}", self.endpoint(), api, kind, model_id)
+    }
+}
+
+#[async_trait]
+impl HubBackend for HuggingFaceHub {
+    fn name(&self) -> &'static str {
+        "huggingface"
+    }
+
+    fn default_revision(&self) -> &'static str {
+        "main"
+    }
+
+    fn default_headers(
+        &self,
+        _options: &DownloadOptions,
+        token: Option<&str>,
+    ) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        let token = token
+            .map(str::to_string)
+            .or_else(|| self.token.clone())
+            .or_else(|| std::env::var(HF_TOKEN_ENV).ok().filter(|t| !t.is_empty()));
+        if let Some(token) = token {
+            headers.insert(AUTHORIZATION, bearer(&token)?);
+        }
+        Ok(headers)
+    }
+
+    /// The tree API, following the `Link` header through the pages.
+    async fn list_files(
+        &self,
+        client: &reqwest::Client,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        let kind = options.repo_type;
+        let mut url = format!(
+            "{}/tree/{}",
+            self.repo_url(model_id, options, true),
+            encode_revision(options.revision())
+        );
+        if let Some(subdir) = options.subdir.as_deref().map(|d| d.trim_matches('/'))
+            && !subdir.is_empty()
+        {
+            url = format!("{}/{}", url, subdir);
+        }
+        if options.recursive {
+            url.push_str("?recursive=true");
+        }
+
+        let mut files = vec![];
+        let mut next = Some(url);
+        while let Some(url) = next.take() {
+            throttle::throttle_api(options.api_min_interval).await;
+            let context = || {
+                format!(
+                    "Failed to get files of {} {} from {}",
+                    kind,
+                    model_id,
+                    redact_url(&url)
+                )
+            };
+            let resp = client.get(&url).send().await.with_context(context)?;
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(anyhow::Error::new(ModelScopeError::from_status(status)).context(
+                    format!(
+                        "Failed to get files of {} {}: HTTP {}\nTip: Maybe the {} ID is incorrect or a token is required",
+                        kind, model_id, status, kind
+                    ),
+                ));
+            }
+            next = next_page(resp.headers());
+            let entries = resp.json::<Vec<HfEntry>>().await.with_context(context)?;
+            files.extend(entries.into_iter().map(|entry| {
+                let (size, sha256) = match entry.lfs {
+                    Some(lfs) => (lfs.size, lfs.oid),
+                    None => (entry.size, String::new()),
+                };
+                let is_dir = entry.r#type == "directory";
+                RepoFile {
+                    name: entry
+                        .path
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    size: if is_dir { 0 } else { size },
+                    sha256,
+                    r#type: if is_dir { "tree" } else { "blob" }.to_string(),
+                    path: entry.path,
+                    committed_date: None,
+                    chunk_hashes: None,
+                }
+            }));
+        }
+        Ok(files)
+    }
+
+    fn file_url(&self, model_id: &str, path: &str, options: &DownloadOptions) -> String {
+        format!(
+            "{}/resolve/{}/{}",
+            self.repo_url(model_id, options, false),
+            encode_revision(options.revision()),
+            path
+        )
+    }
+}
+
+fn bearer(token: &str) -> anyhow::Result<HeaderValue> {
+    let mut value: HeaderValue = format!("Bearer {}", token).parse()?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Branches like `refs/pr/1` are one path segment in Hugging Face URLs.
+fn encode_revision(revision: &str) -> String {
+    revision.replace('/', "%2F")
+}
+
+/// The `rel="next"` URL of a `Link` header.
+fn next_page(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params.contains("rel=\"next\"").then(|| {
+            url.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+    })
+}
@@ -0,0 +1,124 @@
+use crate::RepoFile;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// What a partial download in `<file>.incomplete` is for, saved next to it as
+/// `<file>.incomplete.json`.
+///
+/// A file only appears under its own name once it is complete, so a crash never leaves a
+/// truncated file that looks like a finished one. The sidecar ties the partial content to the
+/// version of the file it belongs to, a partial file of another version is started over.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Sidecar {
+    pub(crate) size: u64,
+    pub(crate) sha256: String,
+    /// Byte ranges of a download over several connections, empty for a sequential download
+    /// whose progress is the length of the partial file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) ranges: Vec<Part>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Part {
+    pub(crate) start: u64,
+    pub(crate) end: u64,
+    /// Bytes from `start` that are synced to disk
+    pub(crate) done: u64,
+}
+
+impl Part {
+    pub(crate) fn next(&self) -> u64 {
+        self.start + self.done
+    }
+}
+
+/// `<file>.incomplete`, where a file is written until all of it is downloaded.
+pub(crate) fn incomplete_path(file_path: &Path) -> PathBuf {
+    with_suffix(file_path, ".incomplete")
+}
+
+fn sidecar_path(file_path: &Path) -> PathBuf {
+    with_suffix(file_path, ".incomplete.json")
+}
+
+fn with_suffix(file_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(file_path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+impl Sidecar {
+    pub(crate) fn new(repo_file: &RepoFile, ranges: Vec<Part>) -> Self {
+        Self {
+            size: repo_file.size,
+            sha256: repo_file.sha256.clone(),
+            ranges,
+        }
+    }
+
+    /// The saved sidecar of a file, whatever version of the file it belongs to.
+    pub(crate) fn read(file_path: &Path) -> Option<Self> {
+        serde_json::from_str(&fs::read_to_string(sidecar_path(file_path)).ok()?).ok()
+    }
+
+    /// The saved sidecar of a file, if it belongs to this version of the file and the partial
+    /// file is still there.
+    pub(crate) fn load(repo_file: &RepoFile, file_path: &Path) -> Option<Self> {
+        let sidecar = Self::read(file_path)?;
+        (sidecar.size == repo_file.size
+            && sidecar.sha256 == repo_file.sha256
+            && incomplete_path(file_path).exists())
+        .then_some(sidecar)
+    }
+
+    /// Replaced atomically, so a crash keeps the previous progress rather than a torn file.
+    pub(crate) fn save(&self, file_path: &Path) -> anyhow::Result<()> {
+        let path = sidecar_path(file_path);
+        let tmp = with_suffix(&path, ".tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub(crate) fn done(&self) -> u64 {
+        self.ranges.iter().map(|r| r.done).sum()
+    }
+}
+
+/// Move a finished download into place and drop its sidecar.
+pub(crate) fn commit(file_path: &Path) -> anyhow::Result<()> {
+    fs::rename(incomplete_path(file_path), file_path)?;
+    remove_if_exists(&sidecar_path(file_path))?;
+    Ok(())
+}
+
+/// Move a complete file that failed verification back to `<file>.incomplete`, so it no longer
+/// looks finished. Without a sidecar it is not resumed, the next download starts over.
+pub(crate) fn set_aside(file_path: &Path) -> anyhow::Result<()> {
+    discard_partial(file_path)?;
+    fs::rename(file_path, incomplete_path(file_path))?;
+    Ok(())
+}
+
+/// Delete a file with its partial download, if any.
+pub(crate) fn discard(file_path: &Path) -> anyhow::Result<()> {
+    remove_if_exists(file_path)?;
+    discard_partial(file_path)
+}
+
+/// Delete the partial download of a file and its sidecar, a complete file is left alone.
+pub(crate) fn discard_partial(file_path: &Path) -> anyhow::Result<()> {
+    remove_if_exists(&incomplete_path(file_path))?;
+    remove_if_exists(&sidecar_path(file_path))?;
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+/// How a downloaded model compares to the repository, see [`ModelScope::verify_model`].
+///
+/// [`ModelScope::verify_model`]: crate::ModelScope::verify_model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    pub model_id: String,
+    /// Size and sha256 match the repository
+    pub ok: Vec<String>,
+    /// In the repository, not on disk
+    pub missing: Vec<String>,
+    /// On disk with a different size or content
+    pub corrupted: Vec<CorruptedFile>,
+    /// On disk, not in the repository
+    pub extra: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorruptedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+impl IntegrityReport {
+    pub fn is_intact(&self) -> bool {
+        self.missing.is_empty() && self.corrupted.is_empty()
+    }
+}
@@ -1,14 +1,108 @@
 use anyhow::{Context, bail};
 use async_trait::async_trait;
-use futures_util::StreamExt;
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env::home_dir;
 use std::fs;
 use std::io::{BufWriter, Seek, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+
+mod api;
+mod approve;
+mod blobstore;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod client;
+mod config;
+mod credentials;
+mod disk;
+mod dns;
+mod endpoints;
+mod error;
+mod events;
+mod fdlimit;
+mod gc;
+mod glob;
+mod hub;
+mod incomplete;
+mod integrity;
+mod lockfile;
+mod manifest;
+mod mirror;
+mod model_info;
+mod netfs;
+mod options;
+mod overall;
+mod parallel;
+mod pinning;
+mod plan;
+mod profile;
+mod progress_file;
+mod progress_json;
+mod repo;
+mod retry;
+mod search;
+mod stats;
+mod summary;
+mod sync;
+mod throttle;
+mod tls;
+mod tree;
+mod upload;
+mod user;
+mod verify;
+mod webhook;
+mod weights;
+
+pub use approve::ApproveFile;
+pub use blobstore::{BlobKeyFn, BlobStore, sha256_key};
+pub use client::{ModelScopeClient, ModelScopeClientBuilder};
+pub use config::Config;
+pub use disk::disk_usage;
+pub use error::ModelScopeError;
+pub use events::DownloadEvent;
+pub use gc::PartialDownload;
+pub use hub::{
+    HF_DEFAULT_ENDPOINT, HF_ENDPOINT_ENV, HF_TOKEN_ENV, HubBackend, HuggingFaceHub, ModelScopeHub,
+    parse_hub_uri,
+};
+pub use integrity::{CorruptedFile, IntegrityReport};
+pub use lockfile::{LOCK_FILE, LockedModel, Lockfile};
+pub use manifest::{LocalFileInfo, LocalModelInfo, MANIFEST_FILE, Manifest, ManifestFile};
+pub use mirror::MirrorSink;
+pub use model_info::ModelInfo;
+pub use options::{
+    CacheLayout, ChecksumFailureMode, DownloadOptions, LockMode, OversizePolicy, RepoType,
+    TlsBackend,
+};
+pub use plan::{DownloadPlan, PlannedAction, PlannedFile};
+pub use profile::Profile;
+pub use progress_file::FileProgressCallback;
+pub use progress_json::JsonProgressCallback;
+pub use repo::{DEFAULT_LICENSE, Visibility};
+pub use search::ModelSummary;
+pub use stats::RepoStats;
+pub use summary::{DownloadSummary, RetryDeadlineExceeded, SkippedFile};
+pub use sync::SyncReport;
+pub use tokio_util::sync::CancellationToken;
+pub use tree::render_tree;
+pub use upload::UploadSummary;
+pub use user::UserInfo;
+pub use verify::{ChecksumMismatch, ChunkHashes};
+pub use webhook::Webhook;
+pub use weights::{WEIGHT_EXTENSIONS, is_weight_file};
+use incomplete::{Sidecar, incomplete_path};
+use overall::{OverallProgress, TrackedCallback};
+use summary::FileOutcome;
+use verify::StreamVerifier;
 
 /// 进度回调 trait
 #[async_trait]
@@ -24,12 +118,41 @@ pub trait ProgressCallback: Send + Sync {
     
     /// 当文件下载失败时调用
     async fn on_file_error(&self, file_name: &str, error: &str);
+
+    /// 当文件下载开始时调用，携带完整的文件信息（路径、sha256、类型等）
+    ///
+    /// 默认转发到 `on_file_start`
+    async fn on_repo_file_start(&self, file: &RepoFile) {
+        self.on_file_start(&file.name, file.size).await;
+    }
+
+    /// 当整体进度更新时调用，按字节而不是文件数计算
+    ///
+    /// `total` 是所有已知大小文件的总字节数，已存在或被跳过的文件会立即计入完成
+    async fn on_overall_progress(&self, _downloaded: u64, _total: u64) {}
+
+    /// 当模型开始下载时调用，此时文件列表已获取，在任何文件开始之前
+    ///
+    /// `total_files` 和 `total_bytes` 包括已存在或将被跳过的文件；
+    /// 同时下载多个模型时每个模型各调用一次
+    async fn on_download_start(&self, _total_files: usize, _total_bytes: u64) {}
+
+    /// 当模型的所有文件都已下载或跳过时调用，下载失败时不会调用
+    async fn on_download_complete(&self, _summary: &DownloadSummary) {}
+
+    /// 当文件不需要下载时调用，代替 `on_file_complete` 或 `on_file_error`，例如本地已是完整文件、
+    /// 本地文件比远程更大或服务器上不存在
+    async fn on_file_skipped(&self, _file_name: &str, _reason: &str) {}
+
+    /// 当文件下载因网络错误或校验失败而重试时调用，`attempt` 从 1 开始
+    async fn on_file_retry(&self, _file_name: &str, _attempt: u32, _error: &str) {}
 }
 
 /// 默认的进度回调实现（使用进度条）
 pub struct ProgressBarCallback {
     bars: Arc<MultiProgress>,
     progress_bars: Arc<Mutex<HashMap<String, ProgressBar>>>,
+    total_bar: Arc<Mutex<Option<ProgressBar>>>,
 }
 
 impl ProgressBarCallback {
@@ -37,6 +160,7 @@ impl ProgressBarCallback {
         Self {
             bars: Arc::new(MultiProgress::new()),
             progress_bars: Arc::new(Mutex::new(HashMap::new())),
+            total_bar: Arc::new(Mutex::new(None)),
         }
     }
 }
@@ -52,6 +176,7 @@ impl Clone for ProgressBarCallback {
         Self {
             bars: self.bars.clone(),
             progress_bars: self.progress_bars.clone(),
+            total_bar: self.total_bar.clone(),
         }
     }
 }
@@ -68,8 +193,12 @@ impl ProgressCallback for ProgressBarCallback {
         }
         
         let bar = ProgressBar::new(file_size);
-        let style = ProgressStyle::default_bar().template(BAR_STYLE).unwrap();
-        bar.set_style(style);
+        // Unknown sizes are shown as a spinner until the real length arrives
+        let style = if file_size > 0 { BAR_STYLE } else { SPINNER_STYLE };
+        bar.set_style(ProgressStyle::default_bar().template(style).unwrap());
+        if file_size == 0 {
+            bar.enable_steady_tick(std::time::Duration::from_millis(120));
+        }
         bar.set_message(file_name.to_string());
         self.bars.add(bar.clone());
         
@@ -77,9 +206,14 @@ impl ProgressCallback for ProgressBarCallback {
         bars.insert(file_name.to_string(), bar);
     }
     
-    async fn on_file_progress(&self, file_name: &str, downloaded: u64, _total: u64) {
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
         let bars = self.progress_bars.lock().unwrap();
         if let Some(bar) = bars.get(file_name) {
+            if total > 0 && bar.length() != Some(total) {
+                bar.disable_steady_tick();
+                bar.set_style(ProgressStyle::default_bar().template(BAR_STYLE).unwrap());
+                bar.set_length(total);
+            }
             bar.set_position(downloaded);
         }
     }
@@ -97,6 +231,35 @@ impl ProgressCallback for ProgressBarCallback {
             bar.abandon();
         }
     }
+
+    async fn on_file_skipped(&self, file_name: &str, _reason: &str) {
+        let mut bars = self.progress_bars.lock().unwrap();
+        if let Some(bar) = bars.remove(file_name) {
+            bar.finish_and_clear();
+        }
+    }
+
+    async fn on_overall_progress(&self, downloaded: u64, total: u64) {
+        let mut total_bar = self.total_bar.lock().unwrap();
+        // 每个模型使用一个新的总进度条
+        if total_bar
+            .as_ref()
+            .is_none_or(|bar| bar.is_finished() && downloaded < total)
+        {
+            let bar = ProgressBar::new(total);
+            bar.set_style(ProgressStyle::default_bar().template(BAR_STYLE).unwrap());
+            bar.set_message("Total");
+            *total_bar = Some(self.bars.insert(0, bar));
+        }
+        if let Some(bar) = total_bar.as_ref()
+            && !bar.is_finished()
+        {
+            bar.set_position(downloaded);
+            if downloaded >= total {
+                bar.finish();
+            }
+        }
+    }
 }
 
 /// 简单的回调实现，只打印进度信息
@@ -125,24 +288,82 @@ impl ProgressCallback for SimpleCallback {
     async fn on_file_error(&self, file_name: &str, error: &str) {
         eprintln!("下载失败: {} - 错误: {}", file_name, error);
     }
+
+    async fn on_file_skipped(&self, file_name: &str, reason: &str) {
+        println!("跳过: {} - {}", file_name, reason);
+    }
+
+    async fn on_file_retry(&self, file_name: &str, attempt: u32, error: &str) {
+        eprintln!("重试: {} - 第 {} 次, 错误: {}", file_name, attempt, error);
+    }
 }
 
-const FILES_URL: &str = "https://modelscope.cn/api/v1/models/<model_id>/repo/files?Recursive=true";
-const DOWNLOAD_URL: &str = "https://modelscope.cn/models/<model_id>/resolve/master/<path>";
-const LOGIN_URL: &str = "https://modelscope.cn/api/v1/login";
+/// 用单个闭包接收下载进度的回调，参数为文件名、已下载字节数和总字节数
+pub struct FnCallback<F>(Arc<F>);
+
+impl<F> FnCallback<F>
+where
+    F: Fn(&str, u64, u64) + Send + Sync + 'static,
+{
+    pub fn new(f: F) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl<F> Clone for FnCallback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[async_trait]
+impl<F> ProgressCallback for FnCallback<F>
+where
+    F: Fn(&str, u64, u64) + Send + Sync + 'static,
+{
+    async fn on_file_start(&self, _file_name: &str, _file_size: u64) {}
+
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
+        (self.0)(file_name, downloaded, total);
+    }
+
+    async fn on_file_complete(&self, _file_name: &str) {}
+
+    async fn on_file_error(&self, _file_name: &str, _error: &str) {}
+}
+
+/// Base URL of the hub, override with [`DownloadOptions::endpoint`]
+pub const DEFAULT_ENDPOINT: &str = "https://modelscope.cn";
+const FILES_URL: &str = "<endpoint>/api/v1/models/<model_id>/repo/files?Recursive=<recursive>";
+const DOWNLOAD_URL: &str = "<endpoint>/models/<model_id>/resolve/<revision>/<path>";
+const DATASET_FILES_URL: &str =
+    "<endpoint>/api/v1/datasets/<model_id>/repo/tree?Recursive=<recursive>";
+const DATASET_DOWNLOAD_URL: &str = "<endpoint>/api/v1/datasets/<model_id>/repo";
+const DATASET_PAGE_SIZE: usize = 500;
+const LOGIN_URL: &str = "<endpoint>/api/v1/login";
+pub(crate) const DEFAULT_REVISION: &str = "master";
+/// Files downloaded at once when the options don't say
+pub(crate) const DEFAULT_JOBS: usize = 8;
+const DEFAULT_IN_FLIGHT_CHUNKS: usize = 16;
+const DEFAULT_FLUSH_INTERVAL: u64 = 16 * 1024 * 1024;
+const LFS_POINTER_PREFIX: &[u8] = b"version https://git-lfs.github.com/spec/";
+/// Largest file [`ModelScope::get_file_bytes`] reads into memory
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
 const DIR: &str = ".modelscope";
+/// Overrides `~/.modelscope`, for environments without a home directory
+pub const HOME_ENV: &str = "MODELSCOPE_HOME";
+/// Base URL of the hub when the options don't set one, for private deployments
+pub const ENDPOINT_ENV: &str = "MODELSCOPE_ENDPOINT";
+/// Access token to use without `login`, e.g. in CI, see [`DownloadOptions::token`]
+pub const TOKEN_ENV: &str = "MODELSCOPE_API_TOKEN";
 const COOKIES_FILE: &str = "cookies";
 
-const UA: (&str, &str) = (
-    "User-Agent",
-    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/89.0.4389.90 Safari/537.36",
-);
+const UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/89.0.4389.90 Safari/537.36";
 pub struct ModelScope;
 
 #[derive(Debug, Deserialize)]
 struct ModelScopeResponse {
     #[serde(rename = "Code")]
-    #[allow(unused)]
     code: i64,
     #[serde(rename = "Success")]
     success: bool,
@@ -157,32 +378,401 @@ struct ModelScopeResponseData {
     #[serde(rename = "Files")]
     files: Vec<RepoFile>,
 }
-#[derive(Debug, Deserialize)]
-struct RepoFile {
+/// An entry of a model repository listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoFile {
+    /// File name, without the directory
     #[serde(rename = "Name")]
-    name: String,
+    pub name: String,
+    /// Path relative to the repository root
     #[serde(rename = "Path")]
-    path: String,
+    pub path: String,
+    /// Size in bytes, 0 for directories
     #[serde(rename = "Size")]
-    size: u64,
-    #[serde(rename = "Sha256")]
-    #[allow(unused)]
-    sha256: String,
+    pub size: u64,
+    /// Hex encoded sha256 of the content, empty for directories
+    #[serde(rename = "Sha256", default)]
+    pub sha256: String,
+    /// `blob` for files and `tree` for directories
     #[serde(rename = "Type")]
-    r#type: String,
+    pub r#type: String,
+    /// Unix timestamp in seconds of the last commit touching the file, if the listing has it
+    #[serde(rename = "CommittedDate", default)]
+    pub committed_date: Option<i64>,
+    /// Per-chunk digests recorded by a verified download of the same version, checked while
+    /// streaming. Never in the listing, they come from the manifest of the model.
+    #[serde(skip)]
+    pub chunk_hashes: Option<ChunkHashes>,
+}
+
+impl RepoFile {
+    pub fn is_file(&self) -> bool {
+        self.r#type == "blob"
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.r#type == "tree"
+    }
+}
+
+/// Receives the local paths of completed files, see [`ModelScope::download_as_completed`]
+type CompletedSender = tokio::sync::mpsc::UnboundedSender<anyhow::Result<PathBuf>>;
+
+/// Extras for a single model download that are not user options
+#[derive(Clone, Default)]
+struct RunHooks {
+    /// Receives the local path of every file once it is complete
+    completed: Option<CompletedSender>,
+    /// Files with the same sha256 in this revision are not downloaded again
+    diff_base: Option<DiffBase>,
+    /// Shared by models downloaded together, so they stay within one open files limit
+    fd_budget: Option<Arc<Semaphore>>,
+    /// Shared by models downloaded together, so the overall progress covers all of them
+    overall: Option<Arc<OverallProgress>>,
+    /// Show files as `<model_id>/<path>`, to tell models apart in a shared display
+    prefix_names: bool,
+    /// Shared by models downloaded together, so they stay within one hashing limit
+    verify_budget: Option<Arc<Semaphore>>,
+}
+
+tokio::task_local! {
+    /// Limits how many local files are hashed at once in a run, see
+    /// [`DownloadOptions::verify_concurrency`]
+    static VERIFY_BUDGET: Arc<Semaphore>;
+    /// HTTP client of the [`ModelScopeClient`] running the operation, reused instead of building
+    /// a new one
+    static CLIENT: reqwest::Client;
+}
+
+#[derive(Clone)]
+struct DiffBase {
+    revision: String,
+    /// Path to sha256 of the files in `revision`
+    sha256: HashMap<String, String>,
+}
+
+const SPINNER_STYLE: &str = "{msg:<30} {spinner} {decimal_bytes:<10} {decimal_bytes_per_sec:<12} {elapsed_precise}";
+/// The endpoint of [`ENDPOINT_ENV`], read once.
+pub(crate) fn env_endpoint() -> Option<&'static str> {
+    static ENDPOINT: OnceLock<Option<String>> = OnceLock::new();
+    ENDPOINT
+        .get_or_init(|| std::env::var(ENDPOINT_ENV).ok().filter(|e| !e.is_empty()))
+        .as_deref()
+}
+
+/// The token of [`TOKEN_ENV`], if set.
+pub(crate) fn env_token() -> Option<String> {
+    std::env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty())
+}
+
+fn download_url(options: &DownloadOptions, model_id: &str, path: &str) -> String {
+    options.hub().file_url(model_id, path, options)
+}
+
+/// Whether the manifest in a model dir records a download of `revision` with every file at its
+/// full size.
+fn is_complete_download(model_dir: &Path, revision: &str) -> bool {
+    let Ok(Some(manifest)) = Manifest::load(model_dir) else {
+        return false;
+    };
+    manifest.revision == revision
+        && manifest.files.iter().all(|f| {
+            fs::metadata(manifest.local_path(model_dir, &f.path))
+                .is_ok_and(|m| manifest::is_finished(m.len(), f.size, f.complete))
+        })
+}
+
+/// Hide query values that may carry signatures or tokens before a URL ends up in logs
+fn redact_url(url: &str) -> String {
+    const SAFE_PARAMS: [&str; 7] = [
+        "FilePath",
+        "PageNumber",
+        "PageSize",
+        "Recursive",
+        "Revision",
+        "Root",
+        "Source",
+    ];
+
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.split('?').next().unwrap_or_default().to_string();
+    };
+    let _ = parsed.set_password(None);
+    if parsed.query().is_some() {
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(k, v)| {
+                let v = if SAFE_PARAMS.contains(&k.as_ref()) {
+                    v.into_owned()
+                } else {
+                    "REDACTED".to_string()
+                };
+                (k.into_owned(), v)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
+
+/// Whether a request failed because the connection was dropped, often a transient TLS glitch
+/// that succeeds right away on a fresh connection
+fn is_connection_reset(err: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(e) = source {
+        if let Some(io) = e.downcast_ref::<std::io::Error>()
+            && matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            )
+        {
+            return true;
+        }
+        // hyper's IncompleteMessage is not exposed as a type
+        if e.to_string().contains("connection closed before message completed") {
+            return true;
+        }
+        source = e.source();
+    }
+    false
 }
 
 const BAR_STYLE: &str = "{msg:<30} {bar} {decimal_bytes:<10} / {decimal_total_bytes:<10} {decimal_bytes_per_sec:<12} {percent:<3}%  {eta_precise}";
 
 impl ModelScope {
-    async fn get_client() -> anyhow::Result<reqwest::Client> {
-        let client = reqwest::Client::builder().connect_timeout(std::time::Duration::from_secs(10));
-        let mut default_headers = reqwest::header::HeaderMap::new();
-        if let Some(cookies) = Self::get_cookies()? {
-            default_headers.insert("Cookie", cookies.parse()?);
+    /// Build the shared client used by every file task of a download.
+    ///
+    /// HTTP/2 is negotiated through ALPN, so concurrent file downloads are multiplexed over a
+    /// single connection when the CDN supports it. Otherwise idle HTTP/1.1 connections are
+    /// kept in the pool and reused by the next file.
+    async fn get_client_with(options: &DownloadOptions) -> anyhow::Result<reqwest::Client> {
+        // The client of a ModelScopeClient carries the ModelScope login, other hubs get their own
+        if options.hub.is_none()
+            && let Ok(client) = CLIENT.try_with(Clone::clone)
+        {
+            return Ok(client);
+        }
+        Ok(Self::client_builder(options, None)?.build()?)
+    }
+
+    /// The client settings every request needs, with the headers of the hub, see
+    /// [`HubBackend::default_headers`].
+    pub(crate) fn client_builder(
+        options: &DownloadOptions,
+        token: Option<&str>,
+    ) -> anyhow::Result<reqwest::ClientBuilder> {
+        let mut client = reqwest::Client::builder()
+            .connect_timeout(std::time::Duration::from_secs(10))
+            .user_agent(UA)
+            .pool_idle_timeout(std::time::Duration::from_secs(90))
+            .pool_max_idle_per_host(32)
+            .tcp_keepalive(std::time::Duration::from_secs(60))
+            .http2_adaptive_window(true)
+            .dns_resolver(dns::CachingResolver);
+        if options.http1_only {
+            client = client.http1_only();
+        }
+        if let Some(proxy) = &options.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .with_context(|| format!("Invalid proxy {}", redact_url(proxy)))?;
+            client = client.proxy(proxy);
+        }
+        client = tls::configure(client, options)?;
+        let default_headers = options.hub().default_headers(options, token)?;
+        Ok(client.default_headers(default_headers))
+    }
+
+    /// The directory models are saved to by default: the `save_dir` of the [`Config`], else
+    /// `~/.modelscope/models`, or `$MODELSCOPE_HOME/models` when [`HOME_ENV`] is set.
+    pub fn default_save_dir() -> anyhow::Result<PathBuf> {
+        match Config::load()?.save_dir {
+            Some(dir) => Ok(dir),
+            None => Dirs::model_dir(),
+        }
+    }
+
+    /// The directory datasets are saved to by default, `~/.modelscope/datasets`.
+    pub fn default_dataset_dir() -> anyhow::Result<PathBuf> {
+        Dirs::dataset_dir()
+    }
+
+    /// List every entry of a model repository, including directories.
+    pub async fn list_files(model_id: &str) -> anyhow::Result<Vec<RepoFile>> {
+        Self::list_files_with_options(model_id, &DownloadOptions::default()).await
+    }
+
+    /// List the entries of a model repository the given options would download from,
+    /// e.g. only the `subdir` subtree.
+    pub async fn list_files_with_options(
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        let (options, model_id) = options.for_model(model_id);
+        let client = Self::get_client_with(&options).await?;
+        Self::fetch_repo_files(&client, model_id, &options).await
+    }
+
+    /// Size, file and directory counts and nesting of a repository, from one listing.
+    pub async fn repo_stats(model_id: &str) -> anyhow::Result<RepoStats> {
+        let files = Self::list_files(model_id).await?;
+        Ok(RepoStats::from_files(&files))
+    }
+
+    async fn fetch_repo_files(
+        client: &reqwest::Client,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        // Checked before any request, so a bad pattern fails fast
+        let regex_filter = match &options.regex_filter {
+            Some(pattern) => Some(
+                regex::Regex::new(pattern)
+                    .with_context(|| format!("Invalid regex filter {:?}", pattern))?,
+            ),
+            None => None,
+        };
+        let glob_filter = glob::GlobFilter::new(&options.include, &options.exclude)?;
+
+        let subdir = options.subdir.as_deref().map(|d| d.trim_matches('/'));
+        let mut files = options.hub().list_files(client, model_id, options).await?;
+        if let Some(subdir) = subdir.filter(|d| !d.is_empty()) {
+            files.retain(|f| {
+                f.path == subdir
+                    || f.path
+                        .strip_prefix(subdir)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            });
+        }
+        if options.prefer_safetensors {
+            weights::prefer_safetensors(&mut files);
+        }
+        if let Some(regex) = &regex_filter {
+            files.retain(|f| f.is_dir() || regex.is_match(&f.path));
+        }
+        if let Some(glob) = &glob_filter {
+            files.retain(|f| f.is_dir() || glob.is_match(&f.path));
+        }
+        if !options.recursive {
+            // Keep the direct children of the root only
+            let depth = subdir.filter(|d| !d.is_empty()).map_or(0, |d| d.split('/').count());
+            files.retain(|f| f.path.split('/').count() <= depth + 1);
         }
-        let client = client.default_headers(default_headers);
-        Ok(client.build()?)
+        Ok(files)
+    }
+
+    /// The unfiltered listing of a ModelScope repository, see [`ModelScopeHub`].
+    pub(crate) async fn list_modelscope_files(
+        client: &reqwest::Client,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        let files_url = match options.repo_type {
+            RepoType::Model => FILES_URL,
+            RepoType::Dataset => DATASET_FILES_URL,
+        };
+        let mut files_url = files_url
+            .replace("<endpoint>", options.endpoint())
+            .replace("<model_id>", model_id)
+            .replace("<recursive>", &options.recursive.to_string());
+        // Ask the server for the subtree only, the listing is still filtered by the caller for
+        // servers that ignore it
+        let subdir = options.subdir.as_deref().map(|d| d.trim_matches('/'));
+        if let Ok(mut url) = reqwest::Url::parse(&files_url) {
+            if let Some(subdir) = subdir {
+                url.query_pairs_mut().append_pair("Root", subdir);
+            }
+            if let Some(revision) = &options.revision {
+                url.query_pairs_mut().append_pair("Revision", revision);
+            }
+            files_url = url.to_string();
+        }
+
+        match options.repo_type {
+            RepoType::Model => Self::fetch_listing(client, &files_url, model_id, options).await,
+            // Dataset listings are paged, a short page is the last one
+            RepoType::Dataset => {
+                let mut files = vec![];
+                for page in 1.. {
+                    let page_url = format!(
+                        "{}&PageNumber={}&PageSize={}",
+                        files_url, page, DATASET_PAGE_SIZE
+                    );
+                    let listing = Self::fetch_listing(client, &page_url, model_id, options).await?;
+                    let last = listing.len() < DATASET_PAGE_SIZE;
+                    files.extend(listing);
+                    if last {
+                        break;
+                    }
+                }
+                Ok(files)
+            }
+        }
+    }
+
+    /// One request for a repository listing, unfiltered.
+    async fn fetch_listing(
+        client: &reqwest::Client,
+        files_url: &str,
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<RepoFile>> {
+        let kind = options.repo_type;
+        throttle::throttle_api(options.api_min_interval).await;
+
+        let context = || {
+            format!(
+                "Failed to get files of {} {} from {}",
+                kind,
+                model_id,
+                redact_url(files_url)
+            )
+        };
+
+        tracing::debug!(model_id, url = %redact_url(files_url), "listing files");
+        let mut resets = 0;
+        let resp = loop {
+            match client.get(files_url).send().await {
+                Err(e) if is_connection_reset(&e) && resets < options.listing_reset_retries => {
+                    resets += 1;
+                    tracing::warn!(
+                        model_id,
+                        error = %e,
+                        "connection reset while listing, retrying"
+                    );
+                }
+                resp => break resp.with_context(context)?,
+            }
+        };
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(anyhow::Error::new(ModelScopeError::from_status(status)).context(format!(
+                "Failed to get files of {} {}: HTTP {} {}\nTip: Maybe the {} ID is incorrect or login is required",
+                kind,
+                model_id,
+                status,
+                resp.text().await?,
+                kind
+            )));
+        }
+
+        let response = resp
+            .json::<ModelScopeResponse>()
+            .await
+            .with_context(context)?;
+        if !response.success {
+            let error = ModelScopeError::Api {
+                code: response.code,
+                message: response.message,
+            };
+            return Err(anyhow::Error::new(error)
+                .context(format!("Failed to get files of {} {}", kind, model_id)));
+        }
+
+        Ok(response.data.with_context(context)?.files)
     }
 
     pub async fn download(model_id: &str, save_dir: impl Into<PathBuf>) -> anyhow::Result<()> {
@@ -194,211 +784,1549 @@ impl ModelScope {
         save_dir: impl Into<PathBuf>,
         callback: C,
     ) -> anyhow::Result<()> {
+        Self::download_with_options(model_id, save_dir, DownloadOptions::default(), callback)
+            .await?;
+        Ok(())
+    }
+
+    /// Download a model and report progress to a closure, called with the file name, the bytes
+    /// downloaded and the file size. See [`FnCallback`].
+    pub async fn download_with_fn<F>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        f: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Fn(&str, u64, u64) + Send + Sync + 'static,
+    {
+        Self::download_with_callback(model_id, save_dir, FnCallback::new(f)).await
+    }
+
+    /// Download several models at the same time into the same save dir.
+    ///
+    /// The callback is shared by all models, so a `ProgressBarCallback` renders every file of the
+    /// batch in one display, with file names prefixed by their model id. The open files limit is
+    /// shared too. Set `api_min_interval` to keep the listing calls polite.
+    pub async fn download_many<C: ProgressCallback + Clone + 'static>(
+        model_ids: &[&str],
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<Vec<DownloadSummary>> {
+        let save_dir = save_dir.into();
+        if options.git_compatible && model_ids.len() > 1 {
+            bail!("A git compatible layout holds a single model, download the models one by one");
+        }
+        let fd_budget = options.file_budget();
+        let verify_budget = Arc::new(Semaphore::new(options.verify_concurrency()));
+        let overall = Arc::new(OverallProgress::default());
+        let downloads = model_ids.iter().map(|model_id| {
+            let hooks = RunHooks {
+                fd_budget: Some(fd_budget.clone()),
+                verify_budget: Some(verify_budget.clone()),
+                overall: Some(overall.clone()),
+                prefix_names: model_ids.len() > 1,
+                ..Default::default()
+            };
+            Self::download_and_notify(
+                model_id,
+                &save_dir,
+                options.clone(),
+                callback.clone(),
+                hooks,
+            )
+        });
+        futures_util::future::try_join_all(downloads).await
+    }
+
+    pub async fn download_with_options<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        Self::download_and_notify(model_id, save_dir, options, callback, RunHooks::default())
+            .await
+    }
+
+    /// Download a dataset repository, like `modelscope/gsm8k`, into `<save_dir>/<dataset_id>`.
+    ///
+    /// Datasets go through the same machinery as models: resume, verification, filters and the
+    /// manifest all work the same way.
+    pub async fn download_dataset<C: ProgressCallback + Clone + 'static>(
+        dataset_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        let options = DownloadOptions {
+            repo_type: RepoType::Dataset,
+            ..options
+        };
+        Self::download_with_options(dataset_id, save_dir, options, callback).await
+    }
+
+    /// Download a model into the default save dir, or a dataset into the default dataset dir,
+    /// and return the directory its files are in, ready to load.
+    ///
+    /// Complete files are not downloaded again. When the hub can't be reached and an earlier
+    /// download of the same revision is complete on disk, its directory is returned anyway.
+    pub async fn snapshot(model_id: &str, options: DownloadOptions) -> anyhow::Result<PathBuf> {
+        let save_dir = match options.repo_type {
+            RepoType::Model => Self::default_save_dir()?,
+            RepoType::Dataset => Self::default_dataset_dir()?,
+        };
+        let (model_dir, revision) = {
+            let (options, model_id) = options.for_model(model_id);
+            (options.model_dir(&save_dir, model_id), options.revision().to_string())
+        };
+        let callback = ProgressBarCallback::default();
+        match Self::download_with_options(model_id, &save_dir, options, callback).await {
+            Ok(_) => Ok(model_dir),
+            Err(e) if retry::is_transient(&e) && is_complete_download(&model_dir, &revision) => {
+                tracing::warn!(
+                    error = format!("{:#}", e),
+                    dir = %model_dir.display(),
+                    "download failed, using the earlier download"
+                );
+                Ok(model_dir)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Bring a downloaded model in line with the repository and report what changed.
+    ///
+    /// Files are compared with the manifest of the last download by sha256: changed files are
+    /// downloaded again, new ones downloaded, and files removed upstream deleted locally.
+    pub async fn sync<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<SyncReport> {
+        let (options, model_id) = options.for_model(model_id);
+        let options = options.into_owned();
+        let save_dir = save_dir.into();
+        let model_dir = options.model_dir(&save_dir, model_id);
+        let previous = Manifest::load(&model_dir)?;
+
+        let client = Self::get_client_with(&options).await?;
+        let repo_files = Self::fetch_repo_files(&client, model_id, &options).await?;
+        let mut report = SyncReport {
+            model_id: model_id.to_string(),
+            ..Default::default()
+        };
+        for repo_file in repo_files.iter().filter(|f| f.is_file()) {
+            let path = repo_file.path.clone();
+            match previous.as_ref().and_then(|m| m.get(&path)) {
+                None => report.added.push(path),
+                Some(f) if !f.sha256.eq_ignore_ascii_case(&repo_file.sha256) => {
+                    // The old content may have the same size, which would pass as complete
+                    if let Some(manifest) = &previous {
+                        let local = manifest.local_path(&model_dir, &path);
+                        if local.exists() {
+                            fs::remove_file(local)?;
+                        }
+                    }
+                    report.updated.push(path);
+                }
+                Some(_) => report.unchanged.push(path),
+            }
+        }
+        // Files left out by the filters still exist upstream, only a full listing tells what was
+        // really removed
+        let filtered = options.subdir.is_some()
+            || options.regex_filter.is_some()
+            || !options.include.is_empty()
+            || !options.exclude.is_empty()
+            || options.prefer_safetensors
+            || !options.recursive;
+        let upstream = if filtered {
+            let unfiltered = DownloadOptions {
+                subdir: None,
+                regex_filter: None,
+                include: vec![],
+                exclude: vec![],
+                prefer_safetensors: false,
+                recursive: true,
+                ..options.clone()
+            };
+            Self::fetch_repo_files(&client, model_id, &unfiltered).await?
+        } else {
+            repo_files.clone()
+        };
+        if let Some(manifest) = &previous {
+            for file in &manifest.files {
+                if upstream.iter().any(|f| f.path == file.path) {
+                    continue;
+                }
+                let local = manifest.local_path(&model_dir, &file.path);
+                if local.exists() {
+                    fs::remove_file(local)?;
+                }
+                report.deleted.push(file.path.clone());
+            }
+        }
+
+        report.summary = Self::download_with_options(model_id, save_dir, options, callback).await?;
+        Ok(report)
+    }
+
+    /// Update a model from one revision to another, only downloading files that changed.
+    ///
+    /// The file listings of both revisions are compared by sha256, files that are the same in
+    /// `from_rev` and already exist locally are left alone.
+    pub async fn download_diff(
+        model_id: &str,
+        from_rev: &str,
+        to_rev: &str,
+        save_dir: impl Into<PathBuf>,
+    ) -> anyhow::Result<DownloadSummary> {
+        Self::download_diff_with_options(
+            model_id,
+            from_rev,
+            to_rev,
+            save_dir,
+            DownloadOptions::default(),
+            ProgressBarCallback::default(),
+        )
+        .await
+    }
+
+    pub async fn download_diff_with_options<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        from_rev: &str,
+        to_rev: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        let (options, model_id) = options.for_model(model_id);
+        let options = options.into_owned();
+        let from = DownloadOptions {
+            revision: Some(from_rev.to_string()),
+            ..options.clone()
+        };
+        let client = Self::get_client_with(&from).await?;
+        let sha256 = Self::fetch_repo_files(&client, model_id, &from)
+            .await?
+            .into_iter()
+            .filter(|f| f.is_file() && !f.sha256.is_empty())
+            .map(|f| (f.path, f.sha256))
+            .collect();
+
+        let options = DownloadOptions {
+            revision: Some(to_rev.to_string()),
+            ..options
+        };
+        let hooks = RunHooks {
+            diff_base: Some(DiffBase {
+                revision: from_rev.to_string(),
+                sha256,
+            }),
+            ..Default::default()
+        };
+        Self::download_and_notify(model_id, save_dir, options, callback, hooks).await
+    }
+
+    /// Download a model and yield the local path of each file as soon as it is complete.
+    ///
+    /// Files arrive in completion order, so e.g. the tokenizer can be loaded while the weights
+    /// are still downloading. A failed download ends the stream with the error. Must be called
+    /// from within a Tokio runtime.
+    pub fn download_as_completed(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+    ) -> impl Stream<Item = anyhow::Result<PathBuf>> + Send + 'static {
+        Self::download_as_completed_with_options(
+            model_id,
+            save_dir,
+            DownloadOptions::default(),
+            ProgressBarCallback::default(),
+        )
+    }
+
+    pub fn download_as_completed_with_options<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> impl Stream<Item = anyhow::Result<PathBuf>> + Send + 'static {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let model_id = model_id.to_string();
+        let save_dir = save_dir.into();
+        // Task locals don't cross into spawned tasks
+        let client = CLIENT.try_with(Clone::clone).ok();
+        tokio::spawn(async move {
+            let hooks = RunHooks {
+                completed: Some(tx.clone()),
+                ..Default::default()
+            };
+            let download = Self::download_and_notify(&model_id, save_dir, options, callback, hooks);
+            let res = match client {
+                Some(client) => CLIENT.scope(client, download).await,
+                None => download.await,
+            };
+            if let Err(e) = res {
+                let _ = tx.send(Err(e));
+            }
+        });
+        futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|item| (item, rx))
+        })
+    }
+
+    async fn download_and_notify<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+        hooks: RunHooks,
+    ) -> anyhow::Result<DownloadSummary> {
+        let (options, model_id) = options.for_model(model_id);
+        let options = options.into_owned();
+        let Some(webhook) = options.webhook.clone() else {
+            return Self::download_with_fallback(model_id, save_dir, options, callback, hooks).await;
+        };
+
+        let client = Self::get_client_with(&options).await?;
+        let start = Instant::now();
+        let result =
+            Self::download_with_fallback(model_id, save_dir, options, callback, hooks).await;
+        webhook
+            .notify(&client, model_id, &result, start.elapsed())
+            .await;
+        result
+    }
+
+    /// Download from the fastest of [`DownloadOptions::endpoints`] and move on to the next one
+    /// when the download fails. Complete files are kept, so the next endpoint resumes.
+    async fn download_with_fallback<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+        hooks: RunHooks,
+    ) -> anyhow::Result<DownloadSummary> {
+        if options.endpoints.is_empty() {
+            return Self::download_model(model_id, save_dir, options, callback, hooks).await;
+        }
+
+        let save_dir = save_dir.into();
+        let client = Self::get_client_with(&options).await?;
+        let endpoints = endpoints::rank_endpoints(&client, &options.endpoints).await;
+        let mut last_error = None;
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            let options = DownloadOptions {
+                endpoint: Some(endpoint.clone()),
+                ..options.clone()
+            };
+            let callback = callback.clone();
+            let hooks = hooks.clone();
+            match Self::download_model(model_id, &save_dir, options, callback, hooks).await {
+                Ok(summary) => return Ok(summary),
+                Err(e) => {
+                    if let Some(next) = endpoints.get(index + 1) {
+                        tracing::warn!(
+                            model_id,
+                            endpoint,
+                            next,
+                            error = format!("{:#}", e),
+                            "download failed, trying the next endpoint"
+                        );
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap())
+    }
+
+    async fn download_model<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+        hooks: RunHooks,
+    ) -> anyhow::Result<DownloadSummary> {
+        let RunHooks {
+            completed,
+            diff_base,
+            fd_budget,
+            overall,
+            prefix_names,
+            verify_budget,
+        } = hooks;
+
         // Model root dir
         let save_dir = save_dir.into();
         fs::create_dir_all(&save_dir)?;
 
-        // Model save dir, like <save_dir>/<model_id>
-        let model_dir = save_dir.join(model_id);
+        // The transformers cache keeps the content in blobs/ and links it into the snapshot
+        let transformers_dir = (!options.git_compatible
+            && options.layout == CacheLayout::Transformers)
+            .then(|| options.transformers_repo_dir(&save_dir, model_id));
+        let mut options = options;
+        if let Some(repo_dir) = &transformers_dir
+            && options.blob_store.is_none()
+        {
+            options.blob_store = Some(BlobStore::new(repo_dir));
+        }
 
-        println!();
-        println!("Downloading model {} to: {}", model_id, model_dir.display());
-        println!();
+        // Model save dir, like <save_dir>/<model_id>, or <save_dir> itself for a git layout
+        let model_dir = options.model_dir(&save_dir, model_id);
+
+        tracing::info!("Downloading model {} to: {}", model_id, model_dir.display());
 
         fs::create_dir_all(&model_dir)?;
 
-        let files_url = FILES_URL.replace("<model_id>", model_id);
+        if let Some(fs_name) = netfs::network_fs(&model_dir) {
+            tracing::warn!(
+                "{} is on a network filesystem ({}), where many concurrent writes and resuming by \
+                 appending to partial files can corrupt downloads. Consider downloading fewer \
+                 files at once, and deleting partial files instead of resuming them.",
+                model_dir.display(),
+                fs_name
+            );
+        }
 
-        let client = Arc::new(Self::get_client().await?);
+        let client = Arc::new(Self::get_client_with(&options).await?);
 
-        let resp = client.get(files_url).send().await?;
+        let repo_files = options
+            .until_cancelled(Self::fetch_repo_files(&client, model_id, &options))
+            .await??;
 
-        if !resp.status().is_success() {
-            bail!(
-                "Failed to get model files: {}\nTip: Maybe the model ID is incorrect or login is required",
-                resp.text().await?
-            );
+        // Add the incoming model save path to the known model paths
+        // This is used when using the list command, which expects <vendor>/<name> directories
+        if !options.git_compatible && options.layout == CacheLayout::ModelScope {
+            Config::append_save_dir(&save_dir)?;
         }
 
-        let response = resp.json::<ModelScopeResponse>().await?;
-        if !response.success {
-            bail!("Failed to get model files: {}", response.message);
+        let mut repo_files: Vec<RepoFile> = repo_files.into_iter().filter(|f| f.is_file()).collect();
+        if options.deterministic {
+            repo_files.sort_by(|a, b| a.path.cmp(&b.path));
         }
 
-        let data = response.data.unwrap();
-        let repo_files = data.files;
+        if let Some(LockMode::Locked(lock_path)) = &options.lock {
+            Lockfile::load(lock_path)?.check(model_id, options.revision(), &repo_files)?;
+        }
+        let total_bytes = repo_files.iter().map(|f| f.size).sum();
+        callback.on_download_start(repo_files.len(), total_bytes).await;
+
+        let mut summary = DownloadSummary::new(model_id);
+
+        let previous = Manifest::load(&model_dir)?;
+        let mut manifest = Manifest::new(model_id, options.revision());
+        manifest.repo_type = options.repo_type;
+        manifest.weights_dir = options.weights_model_dir(model_id);
+        for repo_file in &mut repo_files {
+            // Checked while downloading the same version again
+            if let Some(previous) = &previous {
+                repo_file.chunk_hashes = previous.chunk_hashes(repo_file);
+            }
+            manifest.upsert(repo_file, false);
+        }
 
-        // Add the incoming model save path to the known model paths
-        // This is used when using the list command
-        Config::append_save_dir(&save_dir)?;
+        // Incremental refresh, files that have not changed upstream and exist locally are left alone
+        let mut unchanged = HashMap::new();
+        for repo_file in &repo_files {
+            if !manifest.local_path(&model_dir, &repo_file.path).exists() {
+                continue;
+            }
+            if let Some(base) = &diff_base
+                && base.sha256.get(&repo_file.path) == Some(&repo_file.sha256)
+            {
+                let reason = format!("unchanged since revision {}", base.revision);
+                unchanged.insert(repo_file.path.clone(), reason);
+                continue;
+            }
+            if let Some(since) = options.modified_since
+                && let Some(modified) =
+                    Self::last_modified(&client, &options, model_id, repo_file).await?
+                && modified < since
+            {
+                let reason = "not modified since the given time".to_string();
+                unchanged.insert(repo_file.path.clone(), reason);
+            }
+        }
 
-        let mut tasks = Vec::new();
+        // Ask about every file before anything is streamed, so prompts don't race the downloads
+        let mut denied = HashSet::new();
+        if let Some(approve) = &options.approve {
+            for repo_file in &repo_files {
+                if !unchanged.contains_key(&repo_file.path)
+                    && !approve.should_download(repo_file).await
+                {
+                    denied.insert(repo_file.path.clone());
+                }
+            }
+        }
 
-        for repo_file in repo_files.into_iter().filter(|f| f.r#type == "blob") {
-            let model_id = model_id.to_string();
-            let client = client.clone();
-            let save_dir = model_dir.clone();
-            let callback = callback.clone();
+        // Record what is being downloaded before starting, so an interrupted download can be inspected
+        manifest.save(&model_dir)?;
+
+        let options = Arc::new(options);
+        let overall = overall.unwrap_or_default();
+        overall.add(model_id, &repo_files);
+        let mut pending = vec![];
+        for repo_file in repo_files.iter().cloned() {
+            let repo_file_path = repo_file.path.clone();
+            let name = if prefix_names {
+                format!("{}/{}", model_id, repo_file.path)
+            } else {
+                repo_file.name.clone()
+            };
+
+            // Without a size, an existing file can't be resumed and a zero-length one may be an
+            // interrupted download. Only a finished download recorded by the manifest is kept,
+            // like an unchanged file.
+            if repo_file.size == 0
+                && let Some(previous) = &previous
+                && previous.get(&repo_file_path).is_some_and(|f| f.complete)
+                && let local_path = manifest.local_path(&model_dir, &repo_file_path)
+                && local_path.exists()
+            {
+                if let Some(tx) = &completed {
+                    let _ = tx.send(Ok(local_path));
+                }
+                unchanged.insert(repo_file_path.clone(), "already downloaded".to_string());
+            }
 
-            let task = tokio::spawn(async move {
-                let res = Self::download_file_with_callback(client, model_id, repo_file, save_dir, callback).await;
-                if let Err(e) = res {
-                    bail!("Error downloading file: {}", e);
+            if let Some(reason) = unchanged.get(&repo_file_path) {
+                callback.on_file_skipped(&name, reason).await;
+                overall.finish(model_id, &repo_file, &callback).await;
+                summary.record(&repo_file_path, FileOutcome::Skipped(reason.clone()));
+                continue;
+            }
+            if denied.contains(&repo_file_path) {
+                let reason = "denied by should_download".to_string();
+                callback.on_file_skipped(&name, &reason).await;
+                overall.finish(model_id, &repo_file, &callback).await;
+                summary.record(&repo_file_path, FileOutcome::Skipped(reason));
+                continue;
+            }
+            let file_path = manifest.local_path(&model_dir, &repo_file.path);
+            pending.push((repo_file, name, file_path));
+        }
+        let hooks = RunHooks {
+            completed,
+            fd_budget,
+            overall: Some(overall),
+            verify_budget,
+            ..Default::default()
+        };
+        Self::download_files(&client, model_id, pending, &options, &callback, hooks, &mut summary)
+            .await?;
+
+        for path in &summary.downloaded {
+            manifest.mark_complete(path, options.verify, summary.chunk_hashes.get(path));
+        }
+        // Unchanged files keep what the previous download recorded
+        if let Some(previous) = &previous {
+            for path in unchanged.keys() {
+                if let Some(f) = previous.get(path)
+                    && f.complete
+                {
+                    manifest.mark_complete(path, f.verified, None);
                 }
-                Ok::<(), anyhow::Error>(())
-            });
+            }
+        }
+        manifest.touch();
+        manifest.save(&model_dir)?;
 
-            tasks.push(task);
+        if let Some(repo_dir) = &transformers_dir {
+            let ref_path = repo_dir.join("refs").join(options.revision());
+            if let Some(parent) = ref_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(ref_path, options.revision())?;
         }
-        for task in tasks {
-            task.await??;
+
+        if let Some(LockMode::Update(lock_path)) = &options.lock {
+            let mut lockfile = Lockfile::load(lock_path)?;
+            lockfile.update(model_id, options.revision(), &repo_files);
+            lockfile.save(lock_path)?;
         }
 
-        Ok(())
+        callback.on_download_complete(&summary).await;
+        Ok(summary)
     }
 
-    async fn download_file(
-        client: Arc<reqwest::Client>,
-        model_id: String,
-        repo_file: RepoFile,
-        save_dir: PathBuf,
-        bar: ProgressBar,
+    /// Download files, given with their display name and local path, at the same time or one
+    /// at a time in path order for a deterministic download, and record what happened to them.
+    ///
+    /// The budgets and the overall progress of the hooks are shared with the other models of
+    /// the run, when they are not given each run has its own.
+    async fn download_files<C: ProgressCallback + Clone + 'static>(
+        client: &Arc<reqwest::Client>,
+        model_id: &str,
+        files: Vec<(RepoFile, String, PathBuf)>,
+        options: &Arc<DownloadOptions>,
+        callback: &C,
+        hooks: RunHooks,
+        summary: &mut DownloadSummary,
     ) -> anyhow::Result<()> {
-        let path = &repo_file.path;
-        let name = &repo_file.name;
+        let overall = hooks.overall.unwrap_or_default();
+        let fd_budget = hooks.fd_budget.unwrap_or_else(|| options.file_budget());
+        let verify_budget = hooks
+            .verify_budget
+            .unwrap_or_else(|| Arc::new(Semaphore::new(options.verify_concurrency())));
+        let sequential = options.deterministic;
+        let mut tasks = Vec::new();
+        for (repo_file, name, file_path) in files {
+            let repo_file_path = repo_file.path.clone();
+            let model_id = model_id.to_string();
+            let client = client.clone();
+            let callback = TrackedCallback::new(
+                callback.clone(),
+                &model_id,
+                repo_file.clone(),
+                name,
+                overall.clone(),
+            );
+            let options = options.clone();
+            let fd_budget = fd_budget.clone();
+            let completed = hooks.completed.clone();
+
+            let task = tokio::spawn(VERIFY_BUDGET.scope(verify_budget.clone(), async move {
+                // Files are only opened once a slot is free, so a big batch degrades to lower
+                // concurrency instead of failing with "too many open files"
+                let _permit = options.until_cancelled(fd_budget.acquire_owned()).await??;
+                let outcome = Self::download_file_with_callback(
+                    client,
+                    model_id,
+                    repo_file,
+                    file_path.clone(),
+                    &options,
+                    callback.clone(),
+                )
+                .await?;
+                callback.finish().await;
+                if let FileOutcome::Downloaded(..) = outcome
+                    && let Some(tx) = &completed
+                {
+                    let _ = tx.send(Ok(file_path));
+                }
+                anyhow::Ok(outcome)
+            }));
+
+            // One file at a time, in path order
+            if sequential {
+                summary.record(&repo_file_path, task.await??);
+            } else {
+                tasks.push((repo_file_path, task));
+            }
+        }
+        for (path, task) in tasks {
+            summary.record(&path, task.await??);
+        }
+        Ok(())
+    }
 
-        bar.set_message(name.clone());
+    /// Download a JSON file of a model, like `config.json`, and deserialize it.
+    pub async fn fetch_config<T: DeserializeOwned>(
+        model_id: &str,
+        file_path: &str,
+    ) -> anyhow::Result<T> {
+        Self::fetch_config_with_options(model_id, file_path, &DownloadOptions::default()).await
+    }
 
-        let file_path = save_dir.join(path);
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Like [`fetch_config`](Self::fetch_config), from the endpoint and revision of the options.
+    pub async fn fetch_config_with_options<T: DeserializeOwned>(
+        model_id: &str,
+        file_path: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<T> {
+        let (options, model_id) = options.for_model(model_id);
+        let client = Self::get_client_with(&options).await?;
+        let bytes = Self::fetch_file_bytes(
+            &client,
+            &options,
+            model_id,
+            file_path,
+            DEFAULT_MAX_FILE_BYTES,
+        )
+        .await?;
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse {} of model {}", file_path, model_id))
+    }
+
+    /// Download a small file of a model, like `config.json` or `tokenizer.json`, into memory.
+    /// Fails for files over [`DEFAULT_MAX_FILE_BYTES`].
+    pub async fn get_file_bytes(model_id: &str, file_path: &str) -> anyhow::Result<Vec<u8>> {
+        Self::get_file_bytes_with_options(
+            model_id,
+            file_path,
+            DEFAULT_MAX_FILE_BYTES,
+            &DownloadOptions::default(),
+        )
+        .await
+    }
+
+    /// Like [`get_file_bytes`](Self::get_file_bytes), for files up to `max_size` bytes, from the
+    /// endpoint and revision of the options.
+    pub async fn get_file_bytes_with_options(
+        model_id: &str,
+        file_path: &str,
+        max_size: u64,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (options, model_id) = options.for_model(model_id);
+        let client = Self::get_client_with(&options).await?;
+        Self::fetch_file_bytes(&client, &options, model_id, file_path, max_size).await
+    }
+
+    /// Download a file of a model into memory, failing once it is over `max_size` bytes.
+    async fn fetch_file_bytes(
+        client: &reqwest::Client,
+        options: &DownloadOptions,
+        model_id: &str,
+        file_path: &str,
+        max_size: u64,
+    ) -> anyhow::Result<Vec<u8>> {
+        let context = || format!("Failed to download {} of model {}", file_path, model_id);
+        let too_large = || {
+            anyhow::anyhow!(
+                "The file is larger than {}, download it instead",
+                indicatif::BinaryBytes(max_size)
+            )
+            .context(context())
+        };
+        let resp = Self::request_file(client, options, model_id, file_path).await?;
+        if resp.content_length().is_some_and(|len| len > max_size) {
+            return Err(too_large());
+        }
+        let mut bytes = vec![];
+        let mut stream = resp.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.with_context(context)?;
+            if (bytes.len() + chunk.len()) as u64 > max_size {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
         }
 
-        let mut existing_size = 0;
-        let mut file_options = fs::OpenOptions::new();
-        file_options.write(true).create(true);
+        // A pointer means the hub served the LFS stub instead of the real content
+        if bytes.starts_with(LFS_POINTER_PREFIX) {
+            return Err(anyhow::anyhow!("Got a git-lfs pointer instead of the file content")
+                .context(context()));
+        }
+        Ok(bytes)
+    }
+
+    /// Stream a file of a model as it arrives, without saving it, e.g. to print it or pipe it
+    /// into another consumer.
+    pub async fn stream_file(
+        model_id: &str,
+        file_path: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static> {
+        let (options, model_id) = options.for_model(model_id);
+        let client = Self::get_client_with(&options).await?;
+        let resp = Self::request_file(&client, &options, model_id, file_path).await?;
+        Ok(resp.bytes_stream().map(|chunk| Ok(chunk?)))
+    }
+
+    /// Read a file of a model as it arrives, without saving it, e.g. to pipe it into a
+    /// decompressor, a hasher or a socket with [`tokio::io::copy`].
+    pub async fn open_file(
+        model_id: &str,
+        file_path: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<impl AsyncRead + Send + Unpin + 'static> {
+        let (options, model_id) = options.for_model(model_id);
+        let client = Self::get_client_with(&options).await?;
+        let resp = Self::request_file(&client, &options, model_id, file_path).await?;
+        Ok(tokio_util::io::StreamReader::new(
+            resp.bytes_stream().map(|chunk| chunk.map_err(std::io::Error::other)),
+        ))
+    }
+
+    /// Request a file of a model, failing on an error status.
+    async fn request_file(
+        client: &reqwest::Client,
+        options: &DownloadOptions,
+        model_id: &str,
+        file_path: &str,
+    ) -> anyhow::Result<reqwest::Response> {
+        let url = download_url(options, model_id, file_path);
+        let context = || {
+            format!(
+                "Failed to download {} of model {} from {}",
+                file_path,
+                model_id,
+                redact_url(&url)
+            )
+        };
+
+        let resp = client.get(&url).send().await.with_context(context)?;
+        if !resp.status().is_success() {
+            let error = ModelScopeError::from_status(resp.status());
+            return Err(anyhow::Error::new(error).context(context()));
+        }
+        Ok(resp)
+    }
+
+    /// Continue an interrupted download from the manifest in the model directory.
+    ///
+    /// The listing is not fetched again, only files whose local size differs from the manifest
+    /// are downloaded. With `verify`, files that were not verified yet are checked too.
+    pub async fn resume<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        let (options, model_id) = options.for_model(model_id);
+        let mut options = options.into_owned();
+        let model_dir = options.model_dir(&save_dir.into(), model_id);
+        let mut manifest = Manifest::load(&model_dir)?.with_context(|| {
+            format!(
+                "No manifest found in {}, use download instead",
+                model_dir.display()
+            )
+        })?;
+        // Finish the revision that was started, not whatever the default branch is now
+        options.revision.get_or_insert_with(|| manifest.revision.clone());
+        options.repo_type = manifest.repo_type;
+
+        let mut pending: Vec<RepoFile> = manifest
+            .files
+            .iter()
+            .filter(|f| {
+                let local_size = fs::metadata(manifest.local_path(&model_dir, &f.path))
+                    .map_or(0, |m| m.len());
+                !manifest::is_finished(local_size, f.size, f.complete)
+                    || options.verify && !f.verified
+            })
+            .map(ManifestFile::to_repo_file)
+            .collect();
+        if options.deterministic {
+            pending.sort_by(|a, b| a.path.cmp(&b.path));
+        }
+
+        tracing::info!(
+            "Resuming {} of {} files of model {} in: {}",
+            pending.len(),
+            manifest.files.len(),
+            model_id,
+            model_dir.display()
+        );
+
+        let mut summary = DownloadSummary::new(model_id);
+        let total_bytes = pending.iter().map(|f| f.size).sum();
+        callback.on_download_start(pending.len(), total_bytes).await;
+        if pending.is_empty() {
+            callback.on_download_complete(&summary).await;
+            return Ok(summary);
+        }
+
+        let client = Arc::new(Self::get_client_with(&options).await?);
+        let options = Arc::new(options);
+        let overall = Arc::new(OverallProgress::default());
+        overall.add(model_id, &pending);
+        let pending = pending
+            .into_iter()
+            .map(|repo_file| {
+                let name = repo_file.name.clone();
+                let file_path = manifest.local_path(&model_dir, &repo_file.path);
+                (repo_file, name, file_path)
+            })
+            .collect();
+        let hooks = RunHooks {
+            overall: Some(overall),
+            ..Default::default()
+        };
+        Self::download_files(&client, model_id, pending, &options, &callback, hooks, &mut summary)
+            .await?;
+
+        for path in &summary.downloaded {
+            manifest.mark_complete(path, options.verify, summary.chunk_hashes.get(path));
+        }
+        manifest.touch();
+        manifest.save(&model_dir)?;
+
+        callback.on_download_complete(&summary).await;
+        Ok(summary)
+    }
+
+    /// Check every file of a downloaded model against its sha256 and download again only the
+    /// files that are corrupt or missing, the good files are left untouched. For files whose
+    /// chunk digests a verified download recorded, only the corrupt chunks are fetched again.
+    ///
+    /// Files are fetched at the revision of the last download unless the options name one.
+    pub async fn repair<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        let (options, model_id) = options.for_model(model_id);
+        let mut options = options.into_owned();
+        let save_dir = save_dir.into();
+        let model_dir = options.model_dir(&save_dir, model_id);
+        let mut manifest = Manifest::load(&model_dir)?.with_context(|| {
+            format!(
+                "No manifest found in {}, use download instead",
+                model_dir.display()
+            )
+        })?;
+        // Patch from the revision that was downloaded, not whatever the default branch is now
+        options.revision.get_or_insert_with(|| manifest.revision.clone());
+        options.repo_type = manifest.repo_type;
+
+        tracing::info!(
+            "Verifying {} files of model {} in: {}",
+            manifest.files.len(),
+            model_id,
+            model_dir.display()
+        );
 
-        if file_path.exists() {
-            if let Ok(metadata) = fs::metadata(&file_path) {
-                existing_size = metadata.len();
-                file_options.append(true);
+        // Missing or partial files are left to resume, the others are hashed in parallel
+        let complete: Vec<(&ManifestFile, PathBuf)> = manifest
+            .files
+            .iter()
+            .map(|f| (f, manifest.local_path(&model_dir, &f.path)))
+            .filter(|(f, path)| fs::metadata(path).is_ok_and(|m| m.len() == f.size))
+            .collect();
+        let bar = ProgressBar::new(complete.iter().map(|(f, _)| f.size).sum());
+        if options.quiet {
+            bar.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        bar.set_style(ProgressStyle::default_bar().template(BAR_STYLE).unwrap());
+        bar.set_message("Verifying");
+        let checked: Vec<(ManifestFile, PathBuf, bool)> = futures_util::stream::iter(complete)
+            .map(|(file, file_path)| {
+                let bar = &bar;
+                async move {
+                    let ok = Self::verify_local_file(&file_path, file).await;
+                    bar.inc(file.size);
+                    (file.clone(), file_path, ok)
+                }
+            })
+            .buffer_unordered(options.verify_concurrency())
+            .collect()
+            .await;
+        bar.finish_and_clear();
+
+        let mut good = vec![];
+        let mut patched = 0;
+        let mut corrupt = 0;
+        let mut client = None;
+        for (file, file_path, ok) in &checked {
+            if *ok {
+                good.push(file.path.clone());
+                continue;
             }
-        } else {
-            file_options.truncate(true);
+            tracing::warn!("{} is corrupt", file.path);
+
+            // With chunk digests only the bad ranges are fetched again, if that does not fix
+            // the file it is downloaded from scratch
+            if let Some(chunks) = &file.chunk_hashes {
+                let client = match &client {
+                    Some(client) => client,
+                    None => client.insert(Self::get_client_with(&options).await?),
+                };
+                let url = download_url(&options, model_id, &file.path);
+                if let Err(e) = Self::patch_ranges(client, &url, file_path, chunks, file.size).await
+                {
+                    tracing::warn!(path = file.path, error = format!("{:#}", e), "could not patch");
+                } else if Self::verify_local_file(file_path, file).await {
+                    tracing::info!("Patched the corrupt chunks of {}", file.path);
+                    good.push(file.path.clone());
+                    patched += 1;
+                    continue;
+                }
+            }
+            fs::remove_file(file_path)?;
+            corrupt += 1;
+        }
+        tracing::info!(
+            "{} files are good, {} patched, {} corrupt, {} missing or incomplete",
+            good.len() - patched,
+            patched,
+            corrupt,
+            manifest.files.len() - good.len() - corrupt
+        );
+
+        for path in &good {
+            manifest.mark_complete(path, true, None);
+        }
+        manifest.save(&model_dir)?;
+
+        // Only the files deleted above and the incomplete ones are left to download
+        let options = DownloadOptions {
+            verify: true,
+            ..options
+        };
+        Self::resume(model_id, save_dir, options, callback).await
+    }
+
+    /// Compare a downloaded model with the repository listing: every file is checked for its
+    /// size and sha256, and local files the repository doesn't have are reported as extra.
+    ///
+    /// The listing is fetched at the revision of the last download unless the options name one.
+    /// Nothing is changed on disk, use [`repair`](Self::repair) to fix what is found.
+    pub async fn verify_model(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+    ) -> anyhow::Result<IntegrityReport> {
+        let (options, model_id) = options.for_model(model_id);
+        let mut options = options.into_owned();
+        let save_dir = save_dir.into();
+        let mut model_dir = options.model_dir(&save_dir, model_id);
+        let manifest = Manifest::load(&model_dir)?;
+        if let Some(manifest) = &manifest {
+            options
+                .revision
+                .get_or_insert_with(|| manifest.revision.clone());
+            options.repo_type = manifest.repo_type;
+            model_dir = options.model_dir(&save_dir, model_id);
+        }
+        if !model_dir.is_dir() {
+            bail!("Model {} was not found in {}", model_id, model_dir.display());
+        }
+
+        let client = Self::get_client_with(&options).await?;
+        let repo_files: Vec<RepoFile> = Self::fetch_repo_files(&client, model_id, &options)
+            .await?
+            .into_iter()
+            .filter(|f| f.is_file())
+            .collect();
+        let local_path = |path: &str| match &manifest {
+            Some(manifest) => manifest.local_path(&model_dir, path),
+            None => model_dir.join(path),
+        };
+
+        let mut report = IntegrityReport {
+            model_id: model_id.to_string(),
+            ..Default::default()
+        };
+        let checked: Vec<(String, Option<String>)> = futures_util::stream::iter(&repo_files)
+            .filter_map(|repo_file| {
+                let file_path = local_path(&repo_file.path);
+                let present = fs::metadata(&file_path).map(|m| m.len());
+                if present.is_err() {
+                    report.missing.push(repo_file.path.clone());
+                }
+                // Chunk digests of the last verified download tell where the file is corrupt
+                let chunk_hashes = manifest.as_ref().and_then(|m| m.chunk_hashes(repo_file));
+                let present = present.ok().map(|len| (repo_file, chunk_hashes, file_path, len));
+                futures_util::future::ready(present)
+            })
+            .map(|(repo_file, chunk_hashes, file_path, len)| async move {
+                if len != repo_file.size {
+                    let reason = format!("size is {}, expected {}", len, repo_file.size);
+                    return (repo_file.path.clone(), Some(reason));
+                }
+                let verifier =
+                    StreamVerifier::new(&repo_file.path, &repo_file.sha256, chunk_hashes.as_ref());
+                let verified = Self::verify_local_prefix(&file_path, len, verifier)
+                    .await
+                    .and_then(StreamVerifier::finish);
+                let reason = verified.err().map(|e| match e.downcast_ref::<ChecksumMismatch>() {
+                    Some(mismatch) => mismatch.message.clone(),
+                    None => e.to_string(),
+                });
+                (repo_file.path.clone(), reason)
+            })
+            .buffer_unordered(options.verify_concurrency())
+            .collect()
+            .await;
+        for (path, failure) in checked {
+            match failure {
+                None => report.ok.push(path),
+                Some(reason) => report.corrupted.push(CorruptedFile { path, reason }),
+            }
+        }
+
+        let known: HashSet<PathBuf> = repo_files.iter().map(|f| local_path(&f.path)).collect();
+        let mut dirs = vec![model_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if path.is_dir() {
+                    if name != ".git" {
+                        dirs.push(path);
+                    }
+                    continue;
+                }
+                // Download bookkeeping, not repository content
+                if name == MANIFEST_FILE
+                    || name == LOCK_FILE
+                    || name.ends_with(".incomplete")
+                    || name.ends_with(".incomplete.json")
+                    || known.contains(&path)
+                {
+                    continue;
+                }
+                let relative = path.strip_prefix(&model_dir).unwrap_or(&path);
+                report
+                    .extra
+                    .push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+
+        report.ok.sort();
+        report.missing.sort();
+        report.corrupted.sort_by(|a, b| a.path.cmp(&b.path));
+        report.extra.sort();
+        Ok(report)
+    }
+
+    async fn verify_local_file(file_path: &Path, file: &ManifestFile) -> bool {
+        let verifier = StreamVerifier::new(&file.path, &file.sha256, file.chunk_hashes.as_ref());
+        let verifier = Self::verify_local_prefix(file_path, file.size, verifier).await;
+        verifier.and_then(StreamVerifier::finish).is_ok()
+    }
+
+    /// Download the chunks of a local file that do not match their digests again, in place.
+    async fn patch_ranges(
+        client: &reqwest::Client,
+        url: &str,
+        file_path: &Path,
+        chunks: &ChunkHashes,
+        size: u64,
+    ) -> anyhow::Result<()> {
+        let ranges = {
+            let file_path = file_path.to_path_buf();
+            let chunks = chunks.clone();
+            tokio::task::spawn_blocking(move || verify::corrupt_ranges(&file_path, &chunks, size))
+                .await??
+        };
+        let mut file = fs::OpenOptions::new().write(true).open(file_path)?;
+        for range in ranges {
+            let resp = client
+                .get(url)
+                .header("Range", format!("bytes={}-{}", range.start, range.end - 1))
+                .send()
+                .await?;
+            if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                bail!("the server does not support ranges: HTTP {}", resp.status());
+            }
+            let bytes = resp.bytes().await?;
+            if bytes.len() as u64 != range.end - range.start {
+                bail!(
+                    "expected {} bytes for {:?}, got {}",
+                    range.end - range.start,
+                    range,
+                    bytes.len()
+                );
+            }
+            file.seek(std::io::SeekFrom::Start(range.start))?;
+            file.write_all(&bytes)?;
         }
+        file.sync_data()?;
+        Ok(())
+    }
 
-        let mut file = BufWriter::new(file_options.open(&file_path)?);
-
-        // Set progress bar initial position
-        bar.set_position(existing_size);
-        bar.set_length(repo_file.size);
+    /// When a file last changed upstream, from the listing or else from a HEAD request.
+    async fn last_modified(
+        client: &reqwest::Client,
+        options: &DownloadOptions,
+        model_id: &str,
+        repo_file: &RepoFile,
+    ) -> anyhow::Result<Option<SystemTime>> {
+        if let Some(secs) = repo_file.committed_date {
+            return Ok(Some(UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)));
+        }
 
-        let url = DOWNLOAD_URL
-            .replace("<model_id>", &model_id)
-            .replace("<path>", path);
+        let url = download_url(options, model_id, &repo_file.path);
+        let resp = client.head(&url).send().await.with_context(|| {
+            format!(
+                "Failed to check {} of model {} at {}",
+                repo_file.path,
+                model_id,
+                redact_url(&url)
+            )
+        })?;
+        Ok(resp
+            .headers()
+            .get(reqwest::header::LAST_MODIFIED)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| httpdate::parse_http_date(v).ok()))
+    }
 
-        let mut rb = client.get(&url).header(UA.0, UA.1);
+    #[tracing::instrument(
+        name = "file",
+        skip_all,
+        fields(model_id = %model_id, path = %repo_file.path)
+    )]
+    async fn download_file_with_callback<C: ProgressCallback + Clone + 'static>(
+        client: Arc<reqwest::Client>,
+        model_id: String,
+        repo_file: RepoFile,
+        file_path: PathBuf,
+        options: &DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<FileOutcome> {
+        let url = download_url(options, &model_id, &repo_file.path);
+        let context = || {
+            format!(
+                "Failed to download {} of model {} from {}",
+                repo_file.path,
+                model_id,
+                redact_url(&url)
+            )
+        };
 
-        // Already downloaded, just return ok.
-        // If file size equal repo file size, maybe check sha256
-        // But I think the probability of files having the same number of bytes is relatively low, so I won't check here. 🙊
-        if existing_size == repo_file.size {
-            bar.finish();
-            return Ok(());
+        if let Some(link_from) = &options.link_from {
+            Self::link_from_sibling(link_from, &repo_file, &file_path)
+                .await
+                .with_context(context)?;
         }
 
-        // Resume download
-        if existing_size < repo_file.size {
-            rb = rb.header("Range", format!("bytes={}-", existing_size));
+        // Download into the shared store, then link the blob into the model directory
+        if let Some(blob_path) = options
+            .blob_store
+            .as_ref()
+            .and_then(|store| store.blob_path(&repo_file))
+        {
+            tracing::debug!(blob = %blob_path.display(), "downloading into the blob store");
+            let outcome = Self::download_to_path(
+                &client, &url, &model_id, &repo_file, &blob_path, options, &callback,
+            )
+            .await
+            .with_context(context)?;
+            if let FileOutcome::Downloaded(..) = outcome {
+                BlobStore::link(&blob_path, &file_path).with_context(context)?;
+            }
+            return Ok(outcome);
         }
 
-        let response = rb.send().await?;
-
-        let status = response.status();
+        let outcome = Self::download_to_path(
+            &client, &url, &model_id, &repo_file, &file_path, options, &callback,
+        )
+        .await
+        .with_context(context);
+        match &outcome {
+            Ok(outcome) => tracing::debug!(?outcome, "file done"),
+            Err(e) => tracing::debug!(error = format!("{:#}", e), "file failed"),
+        }
+        outcome
+    }
 
-        // Server doesn't support resume download, re-downloading from beginning
-        // Or existing file size is larger than repo size, re-downloading from beginning
-        if status == reqwest::StatusCode::OK && existing_size > 0 || existing_size > repo_file.size
+    /// Link an identical file from another model directory into `file_path`, so the download
+    /// finds it complete. Candidates are the same path and the manifest entries with the same
+    /// sha256, each is hashed before linking.
+    async fn link_from_sibling(
+        link_from: &Path,
+        repo_file: &RepoFile,
+        file_path: &Path,
+    ) -> anyhow::Result<()> {
+        if repo_file.sha256.is_empty()
+            || fs::metadata(file_path).is_ok_and(|m| m.len() == repo_file.size)
         {
-            file.rewind()?;
-            file.get_ref().set_len(0)?;
-            bar.set_position(0);
+            return Ok(());
         }
 
-        // If status is not success or partial content, bail
-        if !response.status().is_success()
-            && response.status() != reqwest::StatusCode::PARTIAL_CONTENT
-        {
-            bail!(
-                "Failed to download file {}: HTTP {}",
-                name,
-                response.status()
+        let mut candidates = vec![link_from.join(&repo_file.path)];
+        if let Some(manifest) = Manifest::load(link_from)? {
+            candidates.extend(
+                manifest
+                    .files
+                    .iter()
+                    .filter(|f| f.sha256.eq_ignore_ascii_case(&repo_file.sha256))
+                    .map(|f| manifest.local_path(link_from, &f.path)),
             );
         }
 
-        let mut stream = response.bytes_stream();
-
-        while let Some(item) = stream.next().await {
-            let chunk = item?;
-            file.write_all(&chunk)?;
-            bar.inc(chunk.len() as u64);
+        for candidate in candidates {
+            if candidate == file_path
+                || !fs::metadata(&candidate).is_ok_and(|m| m.len() == repo_file.size)
+            {
+                continue;
+            }
+            let verifier = StreamVerifier::new(&repo_file.path, &repo_file.sha256, None);
+            let verifier = Self::verify_local_prefix(&candidate, repo_file.size, verifier).await;
+            if verifier.and_then(StreamVerifier::finish).is_ok() {
+                tracing::debug!(from = %candidate.display(), "linking an identical file");
+                BlobStore::link(&candidate, file_path)?;
+                return Ok(());
+            }
         }
-
-        file.flush()?;
-
-        bar.finish();
-
         Ok(())
     }
 
-    async fn download_file_with_callback<C: ProgressCallback + Clone + 'static>(
-        client: Arc<reqwest::Client>,
-        model_id: String,
-        repo_file: RepoFile,
-        save_dir: PathBuf,
+    /// Download a file from any URL with the resume, streaming and verification of model downloads.
+    ///
+    /// Useful when the caller already has a pre-signed URL for the file. `expected_size` enables
+    /// resuming a partial `dest`, and `expected_sha256` verifies the content once it is complete.
+    pub async fn download_url_to_file<C: ProgressCallback>(
+        url: &str,
+        dest: impl AsRef<Path>,
+        expected_size: Option<u64>,
+        expected_sha256: Option<&str>,
         callback: C,
     ) -> anyhow::Result<()> {
+        let dest = dest.as_ref();
+        let repo_file = RepoFile {
+            name: dest
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: dest.display().to_string(),
+            size: expected_size.unwrap_or(0),
+            sha256: expected_sha256.unwrap_or_default().to_string(),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: None,
+        };
+        let options = DownloadOptions {
+            verify: expected_sha256.is_some(),
+            ..Default::default()
+        };
+
+        let client = Self::get_client_with(&options).await?;
+        Self::download_to_path(&client, url, "", &repo_file, dest, &options, &callback)
+            .await
+            .with_context(|| {
+                format!("Failed to download {} to {}", redact_url(url), dest.display())
+            })?;
+        Ok(())
+    }
+
+    /// Download `url` to `file_path`, handling checksum failures as the options ask.
+    async fn download_to_path<C: ProgressCallback>(
+        client: &reqwest::Client,
+        url: &str,
+        model_id: &str,
+        repo_file: &RepoFile,
+        file_path: &Path,
+        options: &DownloadOptions,
+        callback: &C,
+    ) -> anyhow::Result<FileOutcome> {
+        let started = Instant::now();
+        let deadline_passed = || {
+            options
+                .retry_deadline
+                .is_some_and(|deadline| started.elapsed() >= deadline)
+        };
+        let mut retries = 0;
+        let mut network_retries = 0;
+        let split = options.mirror.is_none()
+            && parallel::should_split(repo_file, file_path, options.connections);
+        loop {
+            let mut res = None;
+            if split {
+                res = Self::download_split(
+                    client,
+                    url,
+                    repo_file,
+                    file_path,
+                    options.connections,
+                    options,
+                    callback,
+                )
+                .await
+                .transpose();
+            }
+            let res = match res {
+                Some(res) => res,
+                None => {
+                    Self::download_file_once(
+                        client, url, model_id, repo_file, file_path, options, callback,
+                    )
+                    .await
+                }
+            };
+            let Err(e) = res else {
+                return res;
+            };
+            if !e.is::<ChecksumMismatch>() {
+                if !retry::is_transient(&e) || network_retries >= options.network_retries {
+                    return Err(e);
+                }
+                if deadline_passed() {
+                    return Err(e.context(RetryDeadlineExceeded {
+                        path: repo_file.path.clone(),
+                        attempts: network_retries + 1,
+                        elapsed: started.elapsed(),
+                    }));
+                }
+                // The partial file is kept, the next attempt resumes from it
+                network_retries += 1;
+                tracing::info!(
+                    attempt = network_retries,
+                    error = format!("{:#}", e),
+                    "retrying after a network error"
+                );
+                callback
+                    .on_file_retry(&repo_file.name, network_retries, &format!("{:#}", e))
+                    .await;
+                options
+                    .until_cancelled(tokio::time::sleep(retry::backoff(network_retries)))
+                    .await?;
+                continue;
+            }
+
+            match options.checksum_failure_mode {
+                ChecksumFailureMode::KeepAndError => return Err(e),
+                ChecksumFailureMode::DeleteAndError => {
+                    incomplete::discard(file_path)?;
+                    return Err(e);
+                }
+                ChecksumFailureMode::Retry(_) if deadline_passed() => {
+                    return Err(e.context(RetryDeadlineExceeded {
+                        path: repo_file.path.clone(),
+                        attempts: retries + 1,
+                        elapsed: started.elapsed(),
+                    }));
+                }
+                ChecksumFailureMode::Retry(max) if retries < max => {
+                    retries += 1;
+                    tracing::info!(attempt = retries, error = %e, "retrying a corrupt download");
+                    incomplete::discard(file_path)?;
+                    callback
+                        .on_file_retry(&repo_file.name, retries, &e.to_string())
+                        .await;
+                }
+                ChecksumFailureMode::Retry(max) => {
+                    return Err(e.context(format!("Still corrupt after {} retries", max)));
+                }
+            }
+        }
+    }
+
+    /// `model_id` is only passed on to the mirror.
+    async fn download_file_once<C: ProgressCallback>(
+        client: &reqwest::Client,
+        url: &str,
+        model_id: &str,
+        repo_file: &RepoFile,
+        file_path: &Path,
+        options: &DownloadOptions,
+        callback: &C,
+    ) -> anyhow::Result<FileOutcome> {
         let path = &repo_file.path;
         let name = &repo_file.name;
 
-        let file_path = save_dir.join(path);
         if let Some(parent) = file_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let mut existing_size = 0;
-        let mut file_options = fs::OpenOptions::new();
-        file_options.write(true).create(true);
-
-        if file_path.exists() {
-            if let Ok(metadata) = fs::metadata(&file_path) {
-                existing_size = metadata.len();
-                file_options.append(true);
+        // A size of 0 in the listing means the size is unknown, the real length is taken
+        // from the response and the file is always downloaded from the beginning
+        let size_known = repo_file.size > 0;
+
+        // The file only gets its name once it is complete, it is written to
+        // `<file>.incomplete` until then
+        let incomplete = incomplete_path(file_path);
+        let final_size = fs::metadata(file_path).ok().map(|m| m.len());
+        // Older versions wrote in place, but a shorter file may as well be a complete older
+        // version. It is only resumed from as far as its content can be checked, the chunks
+        // that match their digests or all of it when the whole file is verified at the end.
+        // A partial download of the new kind is never replaced.
+        if size_known
+            && let Some(len) = final_size.filter(|&len| len < repo_file.size)
+            && !incomplete.exists()
+        {
+            let keep = match &repo_file.chunk_hashes {
+                Some(chunks) => verify::matching_prefix(file_path, chunks, len)?,
+                None if options.verify => len,
+                None => 0,
+            };
+            if keep > 0 {
+                fs::rename(file_path, &incomplete)?;
+                fs::OpenOptions::new()
+                    .write(true)
+                    .open(&incomplete)?
+                    .set_len(keep)?;
+                Sidecar::new(repo_file, vec![]).save(file_path)?;
+            } else {
+                fs::remove_file(file_path)?;
             }
-        } else {
-            file_options.truncate(true);
         }
-
-        let mut file = BufWriter::new(file_options.open(&file_path)?);
-
-        let url = DOWNLOAD_URL
-            .replace("<model_id>", &model_id)
-            .replace("<path>", path);
+        let final_size = final_size.filter(|&len| len >= repo_file.size);
 
         // Now we call on_file_start after checking if file exists
-        callback.on_file_start(name, repo_file.size).await;
-
-        let mut rb = client.get(&url).header(UA.0, UA.1);
+        callback.on_repo_file_start(repo_file).await;
 
         // Already downloaded, just return ok.
-        if existing_size == repo_file.size {
-            callback.on_file_progress(name, repo_file.size, repo_file.size).await;
-            callback.on_file_complete(name).await;
-            return Ok(());
+        if size_known && final_size == Some(repo_file.size) {
+            let mut chunk_hashes = repo_file.chunk_hashes.clone();
+            if options.verify {
+                let verifier =
+                    StreamVerifier::new(path, &repo_file.sha256, repo_file.chunk_hashes.as_ref());
+                let verifier =
+                    Self::verify_local_prefix(file_path, repo_file.size, verifier).await;
+                match verifier.and_then(StreamVerifier::finish) {
+                    Ok(verified) => chunk_hashes = verified.or(chunk_hashes),
+                    Err(e) => {
+                        if e.is::<ChecksumMismatch>() {
+                            incomplete::set_aside(file_path)?;
+                        }
+                        callback.on_file_error(name, &e.to_string()).await;
+                        return Err(e);
+                    }
+                }
+            }
+            if let Some(sink) = &options.mirror {
+                let mirrored = async {
+                    let mut writer = sink.open(model_id, path, repo_file.size).await?;
+                    Self::replay_to_mirror(file_path, repo_file.size, &mut writer).await?;
+                    writer.shutdown().await?;
+                    anyhow::Ok(())
+                };
+                if let Err(e) = mirrored.await {
+                    sink.abort(model_id, path).await;
+                    return Err(e);
+                }
+            }
+            callback.on_file_skipped(name, "already downloaded").await;
+            return Ok(FileOutcome::Downloaded(repo_file.size, chunk_hashes));
+        }
+
+        // The local file may be a different version the user wants to keep
+        if let Some(existing_size) = final_size.filter(|&len| size_known && len > repo_file.size) {
+            match options.oversize_policy {
+                OversizePolicy::Truncate => {}
+                OversizePolicy::Skip => {
+                    let msg = format!(
+                        "local file is larger than remote ({} > {} bytes)",
+                        existing_size, repo_file.size
+                    );
+                    callback.on_file_skipped(name, &msg).await;
+                    return Ok(FileOutcome::Skipped(msg));
+                }
+                OversizePolicy::Error => {
+                    let msg = format!(
+                        "local file is larger than remote ({} > {} bytes)",
+                        existing_size, repo_file.size
+                    );
+                    callback.on_file_error(name, &msg).await;
+                    bail!(
+                        "Refusing to overwrite {}: {}",
+                        file_path.display(),
+                        msg
+                    );
+                }
+            }
+        }
+
+        // Resume from the partial file when it is for this version of the file, the oversized
+        // file above stays in place until the new one replaces it
+        let resumable = Sidecar::load(repo_file, file_path).is_some_and(|s| s.ranges.is_empty());
+        let mut existing_size = match fs::metadata(&incomplete) {
+            Ok(m) if size_known && resumable && m.len() < repo_file.size => m.len(),
+            _ => 0,
+        };
+        let created = !incomplete.exists();
+        let mut file_options = fs::OpenOptions::new();
+        file_options.write(true).create(true);
+        if existing_size > 0 {
+            file_options.append(true);
+        } else {
+            file_options.truncate(true);
+            Sidecar::new(repo_file, vec![]).save(file_path)?;
         }
+        let mut file = BufWriter::new(file_options.open(&incomplete)?);
 
-        // Resume download
-        if existing_size < repo_file.size {
+        let mut rb = client.get(url);
+        if existing_size > 0 {
             rb = rb.header("Range", format!("bytes={}-", existing_size));
         }
 
@@ -406,50 +2334,204 @@ impl ModelScope {
 
         let status = response.status();
 
+        // Without a size from the listing, Content-Length is the only hint, and 0 keeps the
+        // progress indeterminate when the response is chunked
+        let total = if size_known {
+            repo_file.size
+        } else {
+            response.content_length().unwrap_or(0)
+        };
+
         // Server doesn't support resume download, re-downloading from beginning
-        // Or existing file size is larger than repo size, re-downloading from beginning
-        if status == reqwest::StatusCode::OK && existing_size > 0 || existing_size > repo_file.size
-        {
+        // Or the size is unknown, so the partial file can't be trusted
+        if status == reqwest::StatusCode::OK && existing_size > 0 || !size_known {
             file.rewind()?;
             file.get_ref().set_len(0)?;
             existing_size = 0;
-            callback.on_file_progress(name, 0, repo_file.size).await;
+            callback.on_file_progress(name, 0, total).await;
+        }
+
+        // The listing and the storage can briefly disagree, a missing file is not fatal then
+        if status == reqwest::StatusCode::NOT_FOUND && options.skip_missing {
+            drop(file);
+            if created {
+                incomplete::discard(file_path)?;
+            }
+            let msg = "not found on the server (HTTP 404)".to_string();
+            callback.on_file_skipped(name, &msg).await;
+            return Ok(FileOutcome::Skipped(msg));
         }
 
         // If status is not success or partial content, bail
         if !response.status().is_success()
             && response.status() != reqwest::StatusCode::PARTIAL_CONTENT
         {
-            let error_msg = format!("HTTP {}", response.status());
-            callback.on_file_error(name, &error_msg).await;
-            bail!(
-                "Failed to download file {}: HTTP {}",
-                name,
-                response.status()
-            );
+            let error = ModelScopeError::from_status(response.status());
+            callback.on_file_error(name, &error.to_string()).await;
+            return Err(error.into());
         }
 
-        let mut stream = response.bytes_stream();
+        // Only opened once the response is known to be good, so an upload is not left
+        // behind for a file that is skipped or fails right away
+        let mut mirror = match &options.mirror {
+            Some(sink) => Some(sink.open(model_id, path, repo_file.size).await?),
+            None => None,
+        };
+        let res = async {
+            // The mirror must see the whole file, so feed it the bytes we are resuming from
+            if let Some(writer) = mirror.as_mut() {
+                Self::replay_to_mirror(&incomplete, existing_size, writer).await?;
+            }
 
-        while let Some(item) = stream.next().await {
-            let chunk = item?;
-            file.write_all(&chunk)?;
-            existing_size += chunk.len() as u64;
-            callback.on_file_progress(name, existing_size, repo_file.size).await;
-        }
+            // The digest covers the whole file, so the resumed prefix is hashed first
+            let mut verifier = None;
+            if options.verify {
+                let chunks = repo_file.chunk_hashes.as_ref();
+                let v = StreamVerifier::new(path, &repo_file.sha256, chunks);
+                match Self::verify_local_prefix(&incomplete, existing_size, v).await {
+                    Ok(v) => verifier = Some(v),
+                    Err(e) => {
+                        callback.on_file_error(name, &e.to_string()).await;
+                        return Err(e);
+                    }
+                }
+            }
+
+            // Disk writes run on the blocking pool, the bounded channel stops reading from the
+            // network while the disk is behind, so memory stays bounded on slow disks
+            let capacity = options
+                .max_in_flight_chunks
+                .unwrap_or(DEFAULT_IN_FLIGHT_CHUNKS)
+                .max(1);
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Bytes>(capacity);
+            // Sync to disk every so often, so a crash, even of the whole machine, only loses the
+            // progress since the last sync and resuming by length re-downloads little
+            let flush_interval = options
+                .flush_interval
+                .unwrap_or(DEFAULT_FLUSH_INTERVAL)
+                .max(1);
+            let writer = tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+                let mut unflushed = 0;
+                while let Some(chunk) = rx.blocking_recv() {
+                    file.write_all(&chunk)?;
+                    unflushed += chunk.len() as u64;
+                    if unflushed >= flush_interval {
+                        file.flush()?;
+                        file.get_ref().sync_data()?;
+                        unflushed = 0;
+                    }
+                }
+                file.flush()?;
+                file.get_ref().sync_data()
+            });
+
+            let mut stream = response.bytes_stream();
+            let streamed: anyhow::Result<()> = async {
+                while let Some(item) = options.until_cancelled(stream.next()).await? {
+                    let chunk = item?;
+                    if let Some(v) = verifier.as_mut()
+                        && let Err(e) = v.update(&chunk)
+                    {
+                        callback.on_file_error(name, &e.to_string()).await;
+                        return Err(e);
+                    }
+                    if let Some(writer) = mirror.as_mut() {
+                        writer.write_all(&chunk).await?;
+                    }
+                    existing_size += chunk.len() as u64;
+                    // The writer only hangs up when it failed, its error is returned below
+                    if tx.send(chunk).await.is_err() {
+                        break;
+                    }
+                    callback.on_file_progress(name, existing_size, total).await;
+                }
+                Ok(())
+            }
+            .await;
+
+            // Let the writer drain what was received, even on error, so a retry resumes from it
+            drop(tx);
+            writer.await??;
+            streamed?;
+
+            // A corrupt file stays in <file>.incomplete, the checksum failure mode decides what
+            // happens to it. Being whole, the next download starts it over instead of resuming.
+            let mut chunk_hashes = None;
+            if let Some(v) = verifier {
+                match v.finish() {
+                    Ok(verified) => chunk_hashes = verified,
+                    Err(e) => {
+                        callback.on_file_error(name, &e.to_string()).await;
+                        return Err(e);
+                    }
+                }
+            }
+            incomplete::commit(file_path)?;
+
+            if let Some(mut writer) = mirror {
+                writer.shutdown().await?;
+            }
+
+            callback.on_file_complete(name).await;
 
-        file.flush()?;
+            Ok(FileOutcome::Downloaded(existing_size, chunk_hashes))
+        }
+        .await;
+        if res.is_err()
+            && let Some(sink) = &options.mirror
+        {
+            sink.abort(model_id, path).await;
+        }
+        res
+    }
 
-        callback.on_file_complete(name).await;
+    /// Hash the first `len` bytes of a local file on the blocking pool.
+    async fn verify_local_prefix(
+        file_path: &Path,
+        len: u64,
+        mut verifier: StreamVerifier,
+    ) -> anyhow::Result<StreamVerifier> {
+        if len == 0 {
+            return Ok(verifier);
+        }
+        let _permit = match VERIFY_BUDGET.try_with(Arc::clone) {
+            Ok(budget) => Some(budget.acquire_owned().await?),
+            Err(_) => None,
+        };
+        let file_path = file_path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            verify::update_from_file(&mut verifier, &file_path, len)?;
+            Ok(verifier)
+        })
+        .await?
+    }
 
+    /// Copy the first `len` bytes of an already downloaded local file into a mirror writer.
+    async fn replay_to_mirror<W: AsyncWrite + Unpin + ?Sized>(
+        file_path: &Path,
+        len: u64,
+        writer: &mut W,
+    ) -> anyhow::Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let mut local = tokio::fs::File::open(file_path).await?.take(len);
+        tokio::io::copy(&mut local, writer).await?;
         Ok(())
     }
 
     pub async fn login(token: &str) -> anyhow::Result<()> {
-        println!("Logging in...");
-        let client = Self::get_client().await?;
+        Self::login_with_options(token, &DownloadOptions::default()).await
+    }
+
+    /// Log in to the endpoint of the options and keep the login for their
+    /// [`profile`](DownloadOptions::profile). A new profile is created with the endpoint.
+    pub async fn login_with_options(token: &str, options: &DownloadOptions) -> anyhow::Result<()> {
+        options.ensure_modelscope("log in")?;
+        tracing::debug!(endpoint = options.endpoint(), "logging in");
+        let client = Self::get_client_with(options).await?;
         let resp = client
-            .post(LOGIN_URL)
+            .post(LOGIN_URL.replace("<endpoint>", options.endpoint()))
             .json(&serde_json::json!({
                 "AccessToken": token
             }))
@@ -459,7 +2541,9 @@ impl ModelScope {
         let status = resp.status();
 
         if !status.is_success() {
-            bail!("Failed to login: {}", resp.text().await?);
+            let error = ModelScopeError::from_status(status);
+            return Err(anyhow::Error::new(error)
+                .context(format!("Failed to login: {}", resp.text().await?)));
         }
 
         let cookies: serde_json::Value = resp
@@ -467,12 +2551,16 @@ impl ModelScope {
             .map(|cookie| (cookie.name().to_string(), cookie.value().to_string()))
             .collect();
 
-        let dir = Dirs::config_dir()?;
-
-        let cookies_file = dir.join(COOKIES_FILE);
-        fs::write(cookies_file, cookies.to_string())?;
+        credentials::save(options.profile.as_deref(), &cookies.to_string())?;
+        if let Some(name) = &options.profile {
+            let mut profile = Profile::load(name).or_else(|_| Profile::new(name))?;
+            if options.endpoint.is_some() {
+                profile.endpoint = options.endpoint.clone();
+            }
+            profile.save()?;
+        }
 
-        println!("Login successful.");
+        tracing::info!("Login successful.");
 
         Ok(())
     }
@@ -491,59 +2579,107 @@ impl ModelScope {
         save_dir: impl Into<PathBuf>,
         callback: C,
     ) -> anyhow::Result<()> {
+        Self::download_single_file_with_options(
+            model_id,
+            file_path,
+            save_dir,
+            DownloadOptions::default(),
+            callback,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn download_single_file_with_options<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        file_path: &str,
+        save_dir: impl Into<PathBuf>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        Self::download_single_file_as(model_id, file_path, save_dir, None, options, callback).await
+    }
+
+    /// Download a single file and save it as `as_name` in the model directory instead of its
+    /// repository path, e.g. `model.safetensors` as `weights.safetensors`.
+    ///
+    /// A renamed file is not recorded in the manifest, as it is not where the repository has it.
+    pub async fn download_single_file_as<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        file_path: &str,
+        save_dir: impl Into<PathBuf>,
+        as_name: Option<&str>,
+        options: DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<DownloadSummary> {
+        let (options, model_id) = options.for_model(model_id);
+        let options = options.into_owned();
         let save_dir = save_dir.into();
         fs::create_dir_all(&save_dir)?;
 
-        let model_dir = save_dir.join(model_id);
+        let model_dir = options.model_dir(&save_dir, model_id);
         fs::create_dir_all(&model_dir)?;
 
-        println!();
-        println!(
+        tracing::info!(
             "Downloading file {} from model {} to: {}",
             file_path,
             model_id,
             model_dir.display()
         );
-        println!();
 
-        let files_url = FILES_URL.replace("<model_id>", model_id);
-
-        let client = Arc::new(Self::get_client().await?);
+        let client = Arc::new(Self::get_client_with(&options).await?);
 
         // Get file list from API
-        let resp = client.get(files_url).send().await?;
-
-        if !resp.status().is_success() {
-            bail!(
-                "Failed to get model files: {}\nTip: Maybe the model ID is incorrect or login is required",
-                resp.text().await?
-            );
-        }
-
-        let response = resp.json::<ModelScopeResponse>().await?;
-        if !response.success {
-            bail!("Failed to get model files: {}", response.message);
-        }
-
-        let data = response.data.unwrap();
-        let repo_files = data.files;
+        let repo_files = Self::fetch_repo_files(&client, model_id, &options).await?;
 
         // Find the target file
         let repo_file = repo_files
             .into_iter()
-            .find(|f| f.path == file_path && f.r#type == "blob")
+            .find(|f| f.path == file_path && f.is_file())
             .ok_or_else(|| anyhow::anyhow!("File not found in model: {}", file_path))?;
 
-        Self::download_file_with_callback(client, model_id.to_string(), repo_file, model_dir, callback).await?;
+        let mut manifest = Manifest::load(&model_dir)?
+            .unwrap_or_else(|| Manifest::new(model_id, options.revision()));
+        manifest.repo_type = options.repo_type;
+        if options.weights_dir.is_some() {
+            manifest.weights_dir = options.weights_model_dir(model_id);
+        }
+
+        let local_path = match as_name {
+            Some(name) => model_dir.join(name),
+            None => manifest.local_path(&model_dir, &repo_file.path),
+        };
+        let repo_file = RepoFile {
+            chunk_hashes: manifest.chunk_hashes(&repo_file),
+            ..repo_file
+        };
+        let outcome = Self::download_file_with_callback(
+            client,
+            model_id.to_string(),
+            repo_file.clone(),
+            local_path,
+            &options,
+            callback,
+        )
+        .await?;
+
+        let mut summary = DownloadSummary::new(model_id);
+        summary.record(&repo_file.path, outcome);
+        if summary.downloaded.is_empty() || as_name.is_some() {
+            return Ok(summary);
+        }
+
+        manifest.upsert(&repo_file, options.verify);
+        let chunk_hashes = summary.chunk_hashes.get(&repo_file.path);
+        manifest.mark_complete(&repo_file.path, options.verify, chunk_hashes);
+        manifest.touch();
+        manifest.save(&model_dir)?;
 
-        Ok(())
+        Ok(summary)
     }
 
-    fn get_cookies() -> anyhow::Result<Option<String>> {
-        let cookies_file = Dirs::config_dir()?.join(COOKIES_FILE);
-
-        if cookies_file.exists() {
-            let cookies = fs::read_to_string(cookies_file)?;
+    fn get_cookies(profile: Option<&str>) -> anyhow::Result<Option<String>> {
+        if let Some(cookies) = credentials::load(profile)? {
             let cookies: serde_json::Value = serde_json::from_str(&cookies)?;
 
             let cookies = cookies
@@ -560,28 +2696,110 @@ impl ModelScope {
     }
 
     pub async fn logout() -> anyhow::Result<()> {
-        // May just delete cookies file
-        let cookies_file = Dirs::config_dir()?.join(COOKIES_FILE);
-        if cookies_file.exists() {
-            fs::remove_file(cookies_file)?;
-        }
-        println!("Logged out.");
+        Self::logout_with_options(&DownloadOptions::default()).await
+    }
+
+    /// Forget the login of the [`profile`](DownloadOptions::profile) of the options.
+    pub async fn logout_with_options(options: &DownloadOptions) -> anyhow::Result<()> {
+        credentials::delete(options.profile.as_deref())?;
+        tracing::info!("Logged out.");
         Ok(())
     }
 
+    /// Show what has been downloaded for a model, based on its manifest and the files on disk.
+    pub async fn local_info(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+    ) -> anyhow::Result<LocalModelInfo> {
+        Self::local_info_with_options(model_id, save_dir, &DownloadOptions::default()).await
+    }
+
+    /// [`local_info`](Self::local_info) of a model downloaded with these options, which decide
+    /// where its directory is, like `git_compatible` or the transformers layout.
+    pub async fn local_info_with_options(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<LocalModelInfo> {
+        let model_dir = options.model_dir(&save_dir.into(), model_id);
+        let manifest = Manifest::load(&model_dir)?.with_context(|| {
+            format!(
+                "No manifest found in {}, the model was not downloaded or was downloaded by an older version",
+                model_dir.display()
+            )
+        })?;
+
+        let files = manifest
+            .files
+            .iter()
+            .map(|f| LocalFileInfo {
+                path: f.path.clone(),
+                size: f.size,
+                local_size: fs::metadata(manifest.local_path(&model_dir, &f.path))
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+                sha256: f.sha256.clone(),
+                verified: f.verified,
+                complete: f.complete,
+            })
+            .collect();
+
+        Ok(LocalModelInfo {
+            model_id: manifest.model_id.clone(),
+            path: model_dir,
+            revision: manifest.revision.clone(),
+            downloaded_at: manifest.downloaded_at(),
+            total_size: manifest.total_size(),
+            files,
+        })
+    }
+
+    /// Whether a file of a downloaded model is complete on disk, from the manifest and the
+    /// local file size only, without hashing or network calls. Useful as a fast check before
+    /// loading a file or downloading it on demand.
+    pub fn is_file_complete(model_id: &str, file_path: &str, save_dir: impl AsRef<Path>) -> bool {
+        let options = DownloadOptions::default();
+        Self::is_file_complete_with_options(model_id, file_path, save_dir, &options)
+    }
+
+    /// [`is_file_complete`](Self::is_file_complete) for a model downloaded with these options,
+    /// which decide where its directory is, like `git_compatible` or the transformers layout.
+    pub fn is_file_complete_with_options(
+        model_id: &str,
+        file_path: &str,
+        save_dir: impl AsRef<Path>,
+        options: &DownloadOptions,
+    ) -> bool {
+        let model_dir = options.model_dir(save_dir.as_ref(), model_id);
+        let Ok(Some(manifest)) = Manifest::load(&model_dir) else {
+            return false;
+        };
+        let Some(file) = manifest.get(file_path) else {
+            return false;
+        };
+        let local_size = fs::metadata(manifest.local_path(&model_dir, &file.path))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        manifest::is_finished(local_size, file.size, file.complete)
+    }
+
     pub async fn list() -> anyhow::Result<Vec<(String, String)>> {
         // Known model save paths
-        let model_paths = Config::get_known_save_dirs()?;
+        Self::list_in(Config::get_known_save_dirs()?)
+    }
 
+    /// The `<vendor>/<name>` model directories in the save dirs.
+    pub(crate) fn list_in(model_paths: Vec<PathBuf>) -> anyhow::Result<Vec<(String, String)>> {
         let mut models = vec![];
         for model_path in model_paths {
             for dir in fs::read_dir(model_path)? {
                 let dir = dir?;
-                // This level is the model vendor, and the next level is the model name
-                if dir.file_type()?.is_dir() {
+                // This level is the model vendor, and the next level is the model name.
+                // fs::metadata follows symlinks, so linked vendor or model dirs are listed too
+                if fs::metadata(dir.path())?.is_dir() {
                     for entry in fs::read_dir(dir.path())? {
                         let entry = entry?;
-                        if entry.file_type()?.is_dir() {
+                        if fs::metadata(entry.path())?.is_dir() {
                             models.push((
                                 // Model ID
                                 format!(
@@ -590,7 +2808,7 @@ impl ModelScope {
                                     entry.file_name().display()
                                 ),
                                 // Model path
-                                dir.path().display().to_string(),
+                                entry.path().display().to_string(),
                             ));
                         }
                     }
@@ -599,14 +2817,76 @@ impl ModelScope {
         }
         Ok(models)
     }
+
+    /// Directories a model was downloaded to in the known save dirs, in the default layout or
+    /// the `transformers` cache layout.
+    pub fn find_local(model_id: &str) -> anyhow::Result<Vec<PathBuf>> {
+        let transformers = DownloadOptions {
+            layout: CacheLayout::Transformers,
+            ..Default::default()
+        };
+        let mut dirs = vec![];
+        for save_dir in Config::get_known_save_dirs()? {
+            let candidates = [
+                save_dir.join(model_id),
+                transformers.transformers_repo_dir(&save_dir, model_id),
+            ];
+            dirs.extend(candidates.into_iter().filter(|dir| dir.is_dir()));
+        }
+        Ok(dirs)
+    }
+
+    /// Partial files left by interrupted downloads in the known save dirs.
+    pub fn find_partial_downloads() -> anyhow::Result<Vec<PartialDownload>> {
+        let mut found = vec![];
+        for save_dir in Config::get_known_save_dirs()? {
+            gc::scan(&save_dir, None, &mut found)?;
+        }
+        // Known save dirs can be nested in each other
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found.dedup_by(|a, b| a.path == b.path);
+        Ok(found)
+    }
+
+    /// Delete a partial download and its sidecar, the next download of the file starts over.
+    pub fn remove_partial_download(partial: &PartialDownload) -> anyhow::Result<()> {
+        partial.remove()
+    }
+
+    /// Delete a downloaded model directory, and its vendor directory when that is left empty.
+    pub fn remove_local(model_dir: &Path) -> anyhow::Result<()> {
+        fs::remove_dir_all(model_dir)
+            .with_context(|| format!("Failed to delete {}", model_dir.display()))?;
+        let known = Config::get_known_save_dirs()?;
+        if let Some(vendor_dir) = model_dir.parent()
+            && !known.iter().any(|dir| dir == vendor_dir)
+        {
+            // Only succeeds when the dir is empty
+            let _ = fs::remove_dir(vendor_dir);
+        }
+        Ok(())
+    }
 }
 
 struct Dirs {}
 impl Dirs {
+    /// `$MODELSCOPE_HOME`, or `~/.modelscope`
+    fn root() -> anyhow::Result<PathBuf> {
+        if let Some(dir) = std::env::var_os(HOME_ENV).filter(|d| !d.is_empty()) {
+            return Ok(PathBuf::from(dir));
+        }
+        Ok(home_dir()
+            .with_context(|| {
+                format!(
+                    "Failed to get home directory, set {} to the directory for the config and models",
+                    HOME_ENV
+                )
+            })?
+            .join(DIR))
+    }
+
     fn base_dir() -> anyhow::Result<PathBuf> {
-        let base_dir = home_dir()
-            .context("Failed to get home directory")?
-            .join(DIR);
+        let base_dir = Self::root()?;
         if !base_dir.exists() {
             fs::create_dir_all(&base_dir)?;
         }
@@ -621,71 +2901,409 @@ impl Dirs {
         Ok(config_dir)
     }
 
-    #[allow(unused)]
-    pub fn model_dir() -> anyhow::Result<PathBuf> {
-        let model_dir = Self::base_dir()?.join("models");
-        if !model_dir.exists() {
-            fs::create_dir_all(&model_dir)?;
-        }
-        Ok(model_dir)
+    /// Default model save dir, not created until something is downloaded into it
+    fn model_dir() -> anyhow::Result<PathBuf> {
+        Ok(Self::root()?.join("models"))
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Config {
-    known_save_dirs: Vec<PathBuf>,
+    /// Default dataset save dir, created like the model dir
+    fn dataset_dir() -> anyhow::Result<PathBuf> {
+        Ok(Self::root()?.join("datasets"))
+    }
 }
 
-impl Config {
-    const KNOWN_SAVE_DIRS: &'static str = "known_save_dirs";
-    fn append_save_dir(dir: &Path) -> anyhow::Result<()> {
-        let f = Dirs::config_dir()?.join(Self::KNOWN_SAVE_DIRS);
-
-        // Get existing known save dirs
-        let mut known_save_dirs = Self::get_known_save_dirs()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Reads the request head, all requests are GETs without a body. Returns the path and the
+    /// requested range, if any.
+    fn read_request(stream: &std::net::TcpStream) -> (String, Option<(u64, Option<u64>)>) {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let path = line.split(' ').nth(1).unwrap_or_default().to_string();
+        let mut range = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.trim_end().split_once(": ")
+                && name.eq_ignore_ascii_case("range")
+                && let Some((start, end)) = value.trim_start_matches("bytes=").split_once('-')
+            {
+                range = Some((start.parse().unwrap(), end.parse().ok()));
+            }
+        }
+        (path, range)
+    }
 
-        // Canonicalize the directory
-        let dir = dir.canonicalize()?;
+    /// A hub serving `files` of every model and revision, until the test ends. `body` gives the
+    /// content of a file for the request path, `None` answers 404.
+    fn serve(
+        files: Vec<(&'static str, Vec<u8>)>,
+        body: impl Fn(&str) -> Option<Vec<u8>> + Send + 'static,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let listing: Vec<_> = files
+            .iter()
+            .map(|(path, data)| {
+                serde_json::json!({
+                    "Name": path,
+                    "Path": path,
+                    "Size": data.len(),
+                    "Sha256": format!("{:x}", Sha256::digest(data)),
+                    "Type": "blob",
+                })
+            })
+            .collect();
+        let listing = serde_json::json!({
+            "Code": 200,
+            "Success": true,
+            "Message": "",
+            "Data": {"Files": listing},
+        })
+        .to_string();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let (path, range) = read_request(&stream);
+                let (status, headers, content) = if path.contains("/repo/files") {
+                    ("200 OK", String::new(), listing.clone().into_bytes())
+                } else {
+                    match (body(&path), range) {
+                        (None, _) => ("404 Not Found", String::new(), vec![]),
+                        (Some(data), None) => ("200 OK", String::new(), data),
+                        (Some(data), Some((start, end))) => {
+                            let len = data.len() as u64;
+                            let end = end.map_or(len, |end| end + 1);
+                            let headers =
+                                format!("Content-Range: bytes {start}-{}/{len}\r\n", end - 1);
+                            let content = data[start as usize..end as usize].to_vec();
+                            ("206 Partial Content", headers, content)
+                        }
+                    }
+                };
+                let head = format!(
+                    "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    content.len()
+                );
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(&content);
+            }
+        });
+        endpoint
+    }
 
-        if known_save_dirs.contains(&dir) {
-            return Ok(());
+    /// Options of a download from `endpoint` that leaves the user's config alone.
+    fn test_options(endpoint: String) -> DownloadOptions {
+        DownloadOptions {
+            endpoint: Some(endpoint),
+            git_compatible: true,
+            ..Default::default()
         }
+    }
 
-        known_save_dirs.push(dir);
-        fs::write(
-            f,
-            known_save_dirs
-                .iter()
-                .filter(|p| p.exists())
-                .map(|p| p.display().to_string())
-                .filter(|s| !s.trim().is_empty())
-                .collect::<Vec<String>>()
-                .join("\n"),
-        )?;
+    #[tokio::test]
+    async fn listing_is_retried_when_the_connection_is_reset() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            // The first connection is closed without a response
+            let (stream, _) = listener.accept().unwrap();
+            read_request(&stream);
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().unwrap();
+            read_request(&stream);
+            let body = r#"{"Code":200,"Success":true,"Message":"","Data":{"Files":[
+                {"Name":"f.bin","Path":"f.bin","Size":3,"Sha256":"","Type":"blob"}]}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let options = DownloadOptions {
+            endpoint: Some(endpoint),
+            ..Default::default()
+        };
+        let files = ModelScope::list_files_with_options("vendor/model", &options)
+            .await
+            .unwrap();
+        server.join().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "f.bin");
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn recorded_empty_file_is_skipped() {
+        let endpoint = serve(vec![("empty.txt", vec![])], |_| Some(vec![]));
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = test_options(endpoint);
+        let model_dir = options.model_dir(save_dir.path(), "vendor/model");
+        fs::create_dir_all(&model_dir).unwrap();
+        fs::write(model_dir.join("empty.txt"), b"").unwrap();
+        let mut manifest = Manifest::new("vendor/model", DEFAULT_REVISION);
+        let empty = RepoFile {
+            name: "empty.txt".to_string(),
+            path: "empty.txt".to_string(),
+            size: 0,
+            sha256: format!("{:x}", Sha256::digest(b"")),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: None,
+        };
+        manifest.upsert(&empty, false);
+        manifest.mark_complete("empty.txt", false, None);
+        manifest.save(&model_dir).unwrap();
+
+        let summary = ModelScope::download_with_options(
+            "vendor/model",
+            save_dir.path(),
+            options,
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await
+        .unwrap();
+        assert!(summary.downloaded.is_empty());
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].reason, "already downloaded");
+        // Still recorded as finished for the next download
+        let manifest = Manifest::load(&model_dir).unwrap().unwrap();
+        assert!(manifest.get("empty.txt").unwrap().complete);
     }
 
-    fn get_known_save_dirs() -> anyhow::Result<Vec<PathBuf>> {
-        let config_dir = Dirs::config_dir()?;
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)?;
-            return Ok(vec![]);
+    #[tokio::test]
+    async fn corrupt_complete_file_is_kept_as_incomplete() {
+        let endpoint = serve(vec![("f.bin", b"good".to_vec())], |_| Some(b"good".to_vec()));
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = DownloadOptions {
+            verify: true,
+            ..test_options(endpoint)
+        };
+        let file_path = options.model_dir(save_dir.path(), "vendor/model").join("f.bin");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(&file_path, b"bad!").unwrap();
+
+        let res = ModelScope::download_with_options(
+            "vendor/model",
+            save_dir.path(),
+            options,
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await;
+        assert!(res.unwrap_err().is::<ChecksumMismatch>());
+        assert!(!file_path.exists());
+        assert_eq!(fs::read(incomplete_path(&file_path)).unwrap(), b"bad!");
+    }
+
+    #[tokio::test]
+    async fn shorter_file_of_another_version_is_downloaded_again() {
+        let content = b"new version of the file".to_vec();
+        let served = content.clone();
+        let endpoint = serve(vec![("f.bin", content.clone())], move |_| Some(served.clone()));
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = test_options(endpoint);
+        let file_path = options.model_dir(save_dir.path(), "vendor/model").join("f.bin");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        // Complete, but of an older and smaller version, nothing tells it from a partial file
+        fs::write(&file_path, b"old version").unwrap();
+
+        ModelScope::download_with_options(
+            "vendor/model",
+            save_dir.path(),
+            options,
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn partial_download_is_not_replaced_by_a_shorter_file() {
+        let content = b"0123456789abcdefghij".to_vec();
+        let served = content.clone();
+        let endpoint = serve(vec![("f.bin", content.clone())], move |_| Some(served.clone()));
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = test_options(endpoint);
+        let file_path = options.model_dir(save_dir.path(), "vendor/model").join("f.bin");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        let repo_file = RepoFile {
+            name: "f.bin".to_string(),
+            path: "f.bin".to_string(),
+            size: content.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: None,
+        };
+        fs::write(incomplete_path(&file_path), &content[..10]).unwrap();
+        Sidecar::new(&repo_file, vec![]).save(&file_path).unwrap();
+        fs::write(&file_path, b"old").unwrap();
+
+        ModelScope::download_with_options(
+            "vendor/model",
+            save_dir.path(),
+            options,
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+    }
+
+    /// Records what happens to the uploads of a mirror.
+    #[derive(Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl MirrorSink for RecordingSink {
+        async fn open(
+            &self,
+            _model_id: &str,
+            path: &str,
+            _size: u64,
+        ) -> anyhow::Result<Box<dyn AsyncWrite + Send + Unpin>> {
+            self.0.lock().unwrap().push(format!("open {path}"));
+            Ok(Box::new(tokio::io::sink()))
         }
 
-        let f = config_dir.join(Self::KNOWN_SAVE_DIRS);
-        if !f.exists() {
-            return Ok(vec![]);
+        async fn abort(&self, _model_id: &str, path: &str) {
+            self.0.lock().unwrap().push(format!("abort {path}"));
         }
+    }
+
+    #[tokio::test]
+    async fn mirror_uploads_are_not_left_behind() {
+        let sink = Arc::new(RecordingSink::default());
+        let download = |endpoint| {
+            let options = DownloadOptions {
+                mirror: Some(sink.clone()),
+                skip_missing: true,
+                verify: true,
+                ..test_options(endpoint)
+            };
+            let save_dir = tempfile::tempdir().unwrap();
+            async move {
+                let callback = FnCallback::new(|_: &str, _, _| {});
+                let save_dir = save_dir.path();
+                ModelScope::download_with_options("vendor/model", save_dir, options, callback).await
+            }
+        };
 
-        let paths = fs::read_to_string(f)?
-            .lines()
-            .map(PathBuf::from)
-            // Filter out non-existent paths
-            // These paths will be cleaned up when append_save_dir is called
-            .filter(|p| p.exists())
-            .collect::<Vec<_>>();
+        // Not found on the server, no upload is started
+        let endpoint = serve(vec![("missing.bin", b"data".to_vec())], |_| None);
+        download(endpoint).await.unwrap();
+        assert!(sink.0.lock().unwrap().is_empty());
 
-        Ok(paths)
+        // Fails verification once it is streamed, the upload is aborted
+        let endpoint = serve(vec![("bad.bin", b"good".to_vec())], |_| Some(b"bad!".to_vec()));
+        assert!(download(endpoint).await.is_err());
+        assert_eq!(*sink.0.lock().unwrap(), ["open bad.bin", "abort bad.bin"]);
+    }
+
+    #[tokio::test]
+    async fn repair_patches_from_the_downloaded_revision() {
+        let v1 = b"aaaabbbbcccc".to_vec();
+        let requests = Arc::new(std::sync::Mutex::new(vec![]));
+        let endpoint = {
+            let (v1, requests) = (v1.clone(), requests.clone());
+            serve(vec![("f.bin", v1.clone())], move |path| {
+                requests.lock().unwrap().push(path.to_string());
+                // The default branch has moved on since
+                Some(if path.contains("/resolve/v1/") { v1.clone() } else { vec![b'x'; 12] })
+            })
+        };
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = test_options(endpoint);
+        let model_dir = options.model_dir(save_dir.path(), "vendor/model");
+        fs::create_dir_all(&model_dir).unwrap();
+        let mut manifest = Manifest::new("vendor/model", "v1");
+        let chunks = ChunkHashes {
+            chunk_size: 4,
+            sha256: v1.chunks(4).map(|c| format!("{:x}", Sha256::digest(c))).collect(),
+        };
+        manifest.upsert(
+            &RepoFile {
+                name: "f.bin".to_string(),
+                path: "f.bin".to_string(),
+                size: v1.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(&v1)),
+                r#type: "blob".to_string(),
+                committed_date: None,
+                chunk_hashes: None,
+            },
+            false,
+        );
+        manifest.mark_complete("f.bin", true, Some(&chunks));
+        manifest.save(&model_dir).unwrap();
+        fs::write(model_dir.join("f.bin"), b"aaaaXbbbcccc").unwrap();
+
+        let summary = ModelScope::repair(
+            "vendor/model",
+            save_dir.path(),
+            DownloadOptions {
+                quiet: true,
+                ..options
+            },
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read(model_dir.join("f.bin")).unwrap(), v1);
+        // Patched, not downloaded again
+        assert!(summary.downloaded.is_empty());
+        let requests = requests.lock().unwrap();
+        assert!(requests.iter().all(|path| path.contains("/resolve/v1/")), "{requests:?}");
+    }
+
+    #[tokio::test]
+    async fn resume_downloads_the_unfinished_files() {
+        let content = b"content".to_vec();
+        let served = content.clone();
+        let endpoint = serve(vec![], move |_| Some(served.clone()));
+        let save_dir = tempfile::tempdir().unwrap();
+        let options = test_options(endpoint);
+        let model_dir = options.model_dir(save_dir.path(), "vendor/model");
+        fs::create_dir_all(&model_dir).unwrap();
+        let mut manifest = Manifest::new("vendor/model", DEFAULT_REVISION);
+        for path in ["done.bin", "todo.bin"] {
+            let repo_file = RepoFile {
+                name: path.to_string(),
+                path: path.to_string(),
+                size: content.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(&content)),
+                r#type: "blob".to_string(),
+                committed_date: None,
+                chunk_hashes: None,
+            };
+            manifest.upsert(&repo_file, false);
+        }
+        manifest.mark_complete("done.bin", false, None);
+        manifest.save(&model_dir).unwrap();
+        fs::write(model_dir.join("done.bin"), &content).unwrap();
+
+        let summary = ModelScope::resume(
+            "vendor/model",
+            save_dir.path(),
+            options,
+            FnCallback::new(|_: &str, _, _| {}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.downloaded, ["todo.bin"]);
+        assert_eq!(fs::read(model_dir.join("todo.bin")).unwrap(), content);
+        let manifest = Manifest::load(&model_dir).unwrap().unwrap();
+        assert!(manifest.get("todo.bin").unwrap().complete);
     }
 }
@@ -0,0 +1,123 @@
+use crate::RepoFile;
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Default name of the lockfile, looked up in the current directory by the CLI
+pub const LOCK_FILE: &str = "modelscope.lock";
+
+const LOCK_VERSION: u32 = 1;
+
+/// Pins the exact revision and file hashes of downloaded models, like `Cargo.lock`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    pub models: BTreeMap<String, LockedModel>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockedModel {
+    pub revision: String,
+    /// File path to sha256
+    pub files: BTreeMap<String, String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCK_VERSION,
+            models: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Load a lockfile, an empty one if it does not exist yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let lockfile: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if lockfile.version != LOCK_VERSION {
+            bail!(
+                "Unsupported lockfile version {} in {}",
+                lockfile.version,
+                path.display()
+            );
+        }
+        Ok(lockfile)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Record the files of a model, replacing any previous entry.
+    pub fn update(&mut self, model_id: &str, revision: &str, files: &[RepoFile]) {
+        let locked = LockedModel {
+            revision: revision.to_string(),
+            files: files
+                .iter()
+                .filter(|f| f.is_file())
+                .map(|f| (f.path.clone(), f.sha256.clone()))
+                .collect(),
+        };
+        self.models.insert(model_id.to_string(), locked);
+    }
+
+    /// Fail unless the remote files are exactly the ones pinned for the model.
+    pub fn check(&self, model_id: &str, revision: &str, files: &[RepoFile]) -> anyhow::Result<()> {
+        let Some(locked) = self.models.get(model_id) else {
+            bail!(
+                "Model {} is not in the lockfile, run with --update-lock to add it",
+                model_id
+            );
+        };
+        if locked.revision != revision {
+            bail!(
+                "Model {} is locked to revision {}, but {} was requested",
+                model_id,
+                locked.revision,
+                revision
+            );
+        }
+
+        let mut problems = vec![];
+        let mut seen = 0;
+        for file in files.iter().filter(|f| f.is_file()) {
+            match locked.files.get(&file.path) {
+                Some(sha256) if sha256.eq_ignore_ascii_case(&file.sha256) => seen += 1,
+                Some(sha256) => {
+                    seen += 1;
+                    problems.push(format!(
+                        "  {}: locked sha256 {}, remote {}",
+                        file.path, sha256, file.sha256
+                    ))
+                }
+                None => problems.push(format!("  {}: not in the lockfile", file.path)),
+            }
+        }
+        if seen != locked.files.len() {
+            for path in locked.files.keys() {
+                if !files.iter().any(|f| &f.path == path) {
+                    problems.push(format!(
+                        "  {}: locked but no longer in the repository",
+                        path
+                    ));
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            bail!(
+                "Model {} does not match the lockfile:\n{}",
+                model_id,
+                problems.join("\n")
+            );
+        }
+        Ok(())
+    }
+}
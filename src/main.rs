@@ -1,32 +1,174 @@
-use clap::Parser;
-use modelscope_ng::ModelScope;
-use std::env;
-use std::path::PathBuf;
+use async_trait::async_trait;
+use clap::{CommandFactory, Parser};
+use futures_util::StreamExt;
+use indicatif::DecimalBytes;
+use modelscope_ng::{
+    CacheLayout, ChecksumFailureMode, Config, DEFAULT_LICENSE, DownloadOptions, DownloadPlan,
+    DownloadSummary, HuggingFaceHub, JsonProgressCallback, LOCK_FILE, LockMode, MANIFEST_FILE,
+    ModelScope, OversizePolicy, PlannedAction, Profile, ProgressBarCallback, ProgressCallback,
+    RepoFile, TlsBackend, Visibility, Webhook, render_tree,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
 struct Args {
     #[clap(subcommand)]
     command: SubCommand,
+    /// Use the login, endpoint and token of this profile, see login --profile
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// Base URL of the hub, for private deployments. MODELSCOPE_ENDPOINT works too
+    #[arg(long, global = true)]
+    endpoint: Option<String>,
+    /// Proxy for every request, http://, https://, socks5:// or socks5h://, with user:password@
+    /// when it needs a login. HTTPS_PROXY and ALL_PROXY are used without it
+    #[arg(long, global = true)]
+    proxy: Option<String>,
+    /// PEM file of extra root certificates to trust, like the CA of a corporate proxy. Repeat to
+    /// add more
+    #[arg(long, global = true)]
+    ca_cert: Vec<PathBuf>,
+    /// TLS implementation: rustls, or native for the platform TLS library, which needs the
+    /// native-tls feature
+    #[arg(long, global = true, default_value = "rustls")]
+    tls_backend: TlsBackend,
+    /// Skip TLS certificate verification. Anyone on the network path can read and change the
+    /// traffic, only use it as a last resort
+    #[arg(long, global = true)]
+    insecure: bool,
+    /// Hub to list and download from, hf:// and ms:// model IDs pick their own. With huggingface,
+    /// --endpoint is the Hugging Face endpoint and HF_TOKEN its token
+    #[arg(long, global = true, value_enum, default_value_t = Hub::Modelscope)]
+    hub: Hub,
+    /// How progress is shown, json prints one JSON event per line to stdout and nothing else
+    #[arg(long, global = true, value_enum, default_value_t = ProgressFormat::Bars)]
+    progress: ProgressFormat,
+    /// Don't show progress bars, the messages and reports are still printed
+    #[arg(long, global = true)]
+    no_progress: bool,
+    /// Only print errors, warnings and what was asked for, like the output of list or cat.
+    /// Implies --no-progress
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// What to log to stderr, a level like debug or a RUST_LOG filter like modelscope_ng=trace.
+    /// RUST_LOG is used without it, and the banners of the commands and warnings without either
+    #[arg(long, global = true)]
+    log_level: Option<String>,
 }
 
-impl Args {
-    fn default_save_dir() -> PathBuf {
-        let path = env::home_dir().expect("Failed to get home directory");
-        path.join(".modelscope").join("models")
-    }
-}
-
+// Parsed once per run, the size of the download options doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Parser)]
 enum SubCommand {
     /// Download model
     Download {
-        /// Model ID
+        /// Model ID, repeat to download several models
+        #[arg(short, long, required = true)]
+        model_id: Vec<String>,
+        /// The path to save the model, will be created if not exists.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
         #[arg(short, long)]
-        model_id: String,
-        /// The path to save the model, will be created if not exists
-        #[arg(short, long, default_value_os_t = Args::default_save_dir())]
-        save_dir: PathBuf,
+        save_dir: Option<PathBuf>,
+        /// Branch, tag or commit to download, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Only show what would be downloaded, resumed or skipped as already complete
+        #[arg(long)]
+        dry_run: bool,
+        /// Output format of --dry-run
+        #[arg(long, value_enum, default_value_t = ListFormat::Plan)]
+        format: ListFormat,
+        /// Files downloaded at once, 8 by default, lowered to what the open files limit allows
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Connections each file of 64 MiB or more is downloaded over, in byte ranges
+        #[arg(long, default_value_t = 1)]
+        connections: usize,
+        /// Verify the sha256 of every file
+        #[arg(long)]
+        verify: bool,
+        /// Download files one at a time, sorted by path
+        #[arg(long)]
+        deterministic: bool,
+        /// What to do when a local file is larger than the remote one: truncate it and download
+        /// it again, skip it, or keep it and fail with an error
+        #[arg(long, default_value = "truncate")]
+        on_oversize: OversizePolicy,
+        /// Minimum interval between metadata API calls, like 500ms or 2s
+        #[arg(long)]
+        api_interval: Option<humantime::Duration>,
+        /// Refuse to download files whose hashes differ from the lockfile
+        #[arg(long, conflicts_with = "update_lock")]
+        locked: bool,
+        /// Write the downloaded revision and file hashes to the lockfile
+        #[arg(long)]
+        update_lock: bool,
+        /// Path of the lockfile
+        #[arg(long, default_value = LOCK_FILE)]
+        lockfile: PathBuf,
+        /// Skip files that are listed but missing on the server instead of failing
+        #[arg(long)]
+        skip_missing: bool,
+        /// With --verify, what to do with a corrupt file: keep, delete, retry or retry=N
+        #[arg(long, default_value = "keep")]
+        on_checksum_mismatch: ChecksumFailureMode,
+        /// Times a file is tried again after a network error
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
+        /// Stop retrying a file after this long, like 10m
+        #[arg(long)]
+        retry_deadline: Option<humantime::Duration>,
+        /// Skip local files not changed upstream since this time, like 2025-01-01T00:00:00Z
+        #[arg(long)]
+        modified_since: Option<humantime::Timestamp>,
+        /// Pin the certificate of a host as HOST=SHA256, repeat to add more pins
+        #[arg(long, value_parser = parse_tls_pin)]
+        tls_pin: Vec<(String, String)>,
+        /// Save the files directly in save_dir with the repository layout, like git clone
+        #[arg(long)]
+        git_compatible: bool,
+        /// POST a JSON summary of each model download to this URL
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Only download this directory of the repository
+        #[arg(long)]
+        subdir: Option<String>,
+        /// Only download the top-level files, not the subdirectories
+        #[arg(long)]
+        no_recursive: bool,
+        /// Skip the .bin weights when the repository also has .safetensors
+        #[arg(long)]
+        prefer_safetensors: bool,
+        /// Only download files whose path matches this regular expression
+        #[arg(long)]
+        regex: Option<String>,
+        /// Only download files matching this glob, like "*.safetensors", repeat to add more
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, like "*.bin", repeat to add more
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Save weight files (safetensors, bin, gguf, ...) here instead of save_dir
+        #[arg(long)]
+        weights_dir: Option<PathBuf>,
+        /// Link files with the same sha256 from this model directory instead of downloading them
+        #[arg(long)]
+        link_from: Option<PathBuf>,
+        /// Hub endpoint serving the same models, repeat to list several. The fastest reachable
+        /// one is used and the others are fallbacks
+        #[arg(long)]
+        endpoint_mirror: Vec<String>,
+        /// Directory layout under save_dir: modelscope (<save_dir>/<model_id>) or transformers,
+        /// which matches the Hugging Face hub cache
+        #[arg(long, default_value = "modelscope")]
+        layout: CacheLayout,
     },
     /// Download a single file from a model
     DownloadFile {
@@ -36,9 +178,223 @@ enum SubCommand {
         /// File path in the model repository
         #[arg(short, long)]
         file_path: String,
-        /// The path to save the file, will be created if not exists
-        #[arg(short, long, default_value_os_t = Args::default_save_dir())]
-        save_dir: PathBuf,
+        /// The path to save the file, will be created if not exists.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Branch, tag or commit to download, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Connections the file is downloaded over in byte ranges, if it is 64 MiB or more
+        #[arg(long, default_value_t = 1)]
+        connections: usize,
+        /// Verify the sha256 of the file
+        #[arg(long)]
+        verify: bool,
+        /// Don't fail when the file is listed but missing on the server
+        #[arg(long)]
+        skip_missing: bool,
+        /// Save the file under this name in the model directory instead of its repository path
+        #[arg(short, long)]
+        output: Option<String>,
+        /// With --verify, what to do with a corrupt file: keep, delete, retry or retry=N
+        #[arg(long, default_value = "keep")]
+        on_checksum_mismatch: ChecksumFailureMode,
+    },
+    /// Download a dataset
+    DownloadDataset {
+        /// Dataset ID, like modelscope/gsm8k
+        #[arg(short, long)]
+        dataset_id: String,
+        /// The path to save the dataset, will be created if not exists.
+        /// ~/.modelscope/datasets by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Branch, tag or commit to download, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Files downloaded at once, 8 by default, lowered to what the open files limit allows
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Verify the sha256 of every file
+        #[arg(long)]
+        verify: bool,
+        /// Only download this directory of the repository
+        #[arg(long)]
+        subdir: Option<String>,
+        /// Only download files matching this glob, like "*.parquet", repeat to add more
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, repeat to add more
+        #[arg(long)]
+        exclude: Vec<String>,
+    },
+    /// Resume an interrupted download from its manifest, without fetching the file list again
+    Resume {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// The path the model was saved to.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Also verify the sha256 of files that were not verified yet
+        #[arg(long)]
+        verify: bool,
+        /// The model was downloaded with --git-compatible into save_dir itself
+        #[arg(long)]
+        git_compatible: bool,
+        /// Files downloaded at once, 8 by default, lowered to what the open files limit allows
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Verify every file of a downloaded model and download again only the corrupt or missing ones
+    Repair {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// The path the model was saved to.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// The model was downloaded with --git-compatible into save_dir itself
+        #[arg(long)]
+        git_compatible: bool,
+        /// Files hashed at once, the number of CPUs by default
+        #[arg(long)]
+        verify_concurrency: Option<usize>,
+    },
+    /// Check a downloaded model against the repository and report missing, corrupted and extra files
+    Verify {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// The path the model was saved to.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Branch, tag or commit to check against, the downloaded one by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// The model was downloaded with --git-compatible into save_dir itself
+        #[arg(long)]
+        git_compatible: bool,
+        /// Files hashed at once, the number of CPUs by default
+        #[arg(long)]
+        verify_concurrency: Option<usize>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Update a downloaded model to the repository and show what changed
+    Sync {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// The path the model was saved to.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Branch, tag or commit to update to, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Verify the sha256 of every file
+        #[arg(long)]
+        verify: bool,
+        /// Files downloaded at once, 8 by default, lowered to what the open files limit allows
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Show information about a model
+    Info {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// Show what has been downloaded locally
+        #[arg(long)]
+        local: bool,
+        /// With --local, the model was downloaded with --git-compatible into save_dir itself
+        #[arg(long)]
+        git_compatible: bool,
+        /// The path the model was saved to.
+        /// The save_dir of config.toml, else ~/.modelscope/models, by default
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+    },
+    /// List the files of a model repository
+    LsFiles {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// Branch, tag or commit to list, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Only list this directory of the repository
+        #[arg(long)]
+        subdir: Option<String>,
+        /// Show the files as a tree with file and directory sizes
+        #[arg(long, conflicts_with = "json")]
+        tree: bool,
+        /// Print the files as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a file of a model repository to stdout without downloading the model
+    Cat {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// Path of the file in the repository
+        #[arg(short, long)]
+        file: String,
+        /// Branch, tag or commit to read from, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+    },
+    /// Upload the files of a local directory to an existing model repository
+    Upload {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// Directory to upload, its layout is kept in the repository
+        #[arg(short, long)]
+        path: PathBuf,
+        /// Branch to commit to, master by default
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Commit message
+        #[arg(long, default_value = "Upload with modelscope-ng")]
+        message: String,
+        /// Files uploaded at once
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+    /// Create an empty model repository, to upload to
+    CreateRepo {
+        /// Model ID, the owner is your user name or one of your organizations
+        #[arg(short, long)]
+        model_id: String,
+        /// Who can see and download the model: private to the owner and its organization,
+        /// internal to logged in users, or public
+        #[arg(long, default_value = "private")]
+        visibility: Visibility,
+        /// License, as the hub names it
+        #[arg(long, default_value = DEFAULT_LICENSE)]
+        license: String,
+        /// Name shown on the Chinese site, the model name by default
+        #[arg(long)]
+        chinese_name: Option<String>,
+    },
+    /// Search the hub for models
+    Search {
+        /// Words to search for
+        query: String,
+        /// Maximum number of models to show
+        #[arg(short, long, default_value_t = 20)]
+        limit: usize,
+        /// Print the models as JSON
+        #[arg(long)]
+        json: bool,
     },
     /// Login to modelscope use your token
     Login {
@@ -48,32 +404,926 @@ enum SubCommand {
     },
     /// Logout
     Logout,
+    /// Show the user the stored login belongs to
+    Whoami,
     /// List all local models
-    List,
+    List {
+        /// Print the models as JSON, with their size, file count and last modification
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the disk usage of every local model and the total
+    Du {
+        /// Order of the models
+        #[arg(long, value_enum, default_value_t = DuSort::Size)]
+        sort: DuSort,
+    },
+    /// Find partial files left by interrupted downloads in the known save dirs and delete them
+    Gc {
+        /// Resume the downloads of the partial files instead of deleting them
+        #[arg(long)]
+        resume: bool,
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Delete a downloaded model
+    Rm {
+        /// Model ID
+        #[arg(short, long)]
+        model_id: String,
+        /// Only delete the copy in this save dir, instead of looking in all known save dirs
+        #[arg(short, long)]
+        save_dir: Option<PathBuf>,
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print the shell completion script, e.g. `source <(modelscope-ng completions bash)`
+    Completions {
+        /// Shell to complete in
+        shell: clap_complete::Shell,
+    },
+}
+
+/// Hub selected with --hub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Hub {
+    Modelscope,
+    Huggingface,
+}
+
+/// Progress display selected with --progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ProgressFormat {
+    /// Progress bars on the terminal
+    Bars,
+    /// Newline-delimited JSON events on stdout, for wrapping the CLI in other tools
+    Json,
+}
+
+/// The callback of the progress format
+#[derive(Clone)]
+enum Progress {
+    Bars(ProgressBarCallback),
+    Json(JsonProgressCallback),
+    /// --no-progress or --quiet
+    Hidden(NoProgress),
+}
+
+impl Progress {
+    fn new(args: &Args) -> Self {
+        match args.progress {
+            ProgressFormat::Json => Self::Json(JsonProgressCallback::new()),
+            _ if args.no_progress || args.quiet => Self::Hidden(NoProgress),
+            ProgressFormat::Bars => Self::Bars(ProgressBarCallback::new()),
+        }
+    }
+
+    fn inner(&self) -> &dyn ProgressCallback {
+        match self {
+            Self::Bars(callback) => callback,
+            Self::Json(callback) => callback,
+            Self::Hidden(callback) => callback,
+        }
+    }
+}
+
+/// Shows nothing.
+#[derive(Clone)]
+struct NoProgress;
+
+#[async_trait]
+impl ProgressCallback for NoProgress {
+    async fn on_file_start(&self, _file_name: &str, _file_size: u64) {}
+
+    async fn on_file_progress(&self, _file_name: &str, _downloaded: u64, _total: u64) {}
+
+    async fn on_file_complete(&self, _file_name: &str) {}
+
+    async fn on_file_error(&self, _file_name: &str, _error: &str) {}
+}
+
+#[async_trait]
+impl ProgressCallback for Progress {
+    async fn on_file_start(&self, file_name: &str, file_size: u64) {
+        self.inner().on_file_start(file_name, file_size).await;
+    }
+
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
+        self.inner()
+            .on_file_progress(file_name, downloaded, total)
+            .await;
+    }
+
+    async fn on_file_complete(&self, file_name: &str) {
+        self.inner().on_file_complete(file_name).await;
+    }
+
+    async fn on_file_error(&self, file_name: &str, error: &str) {
+        self.inner().on_file_error(file_name, error).await;
+    }
+
+    async fn on_repo_file_start(&self, file: &RepoFile) {
+        self.inner().on_repo_file_start(file).await;
+    }
+
+    async fn on_overall_progress(&self, downloaded: u64, total: u64) {
+        self.inner().on_overall_progress(downloaded, total).await;
+    }
+
+    async fn on_download_start(&self, total_files: usize, total_bytes: u64) {
+        self.inner()
+            .on_download_start(total_files, total_bytes)
+            .await;
+    }
+
+    async fn on_download_complete(&self, summary: &DownloadSummary) {
+        self.inner().on_download_complete(summary).await;
+    }
+
+    async fn on_file_skipped(&self, file_name: &str, reason: &str) {
+        self.inner().on_file_skipped(file_name, reason).await;
+    }
+
+    async fn on_file_retry(&self, file_name: &str, attempt: u32, error: &str) {
+        self.inner().on_file_retry(file_name, attempt, error).await;
+    }
+}
+
+/// How the files of a repository are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ListFormat {
+    /// The action of every file, download, resume or skip, with the bytes left to download
+    Plan,
+    /// A tree with file and directory sizes
+    Tree,
+    /// `path,size,sha256,type` rows, prefixed with the model ID when listing several models
+    Csv,
+}
+
+/// Order of the models printed by `du`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum DuSort {
+    /// Largest first
+    Size,
+    /// By model ID
+    Name,
+}
+
+fn print_plan(plan: &DownloadPlan) {
+    println!();
+    println!("{} -> {}", plan.model_id, plan.model_dir.display());
+    println!();
+    for file in &plan.files {
+        let (action, note) = match file.action {
+            PlannedAction::Download => ("download", String::new()),
+            PlannedAction::Resume => (
+                "resume",
+                format!(" ({} left)", DecimalBytes(file.remaining)),
+            ),
+            PlannedAction::Skip => ("skip", " (already complete)".to_string()),
+        };
+        println!(
+            "  {:<9} {:>12}  {}{}",
+            action,
+            DecimalBytes(file.size).to_string(),
+            file.path,
+            note
+        );
+    }
+    println!();
+    println!(
+        "{} to download ({}), {} to resume ({} left), {} already complete",
+        plan.count(PlannedAction::Download),
+        DecimalBytes(plan.remaining(PlannedAction::Download)),
+        plan.count(PlannedAction::Resume),
+        DecimalBytes(plan.remaining(PlannedAction::Resume)),
+        plan.count(PlannedAction::Skip)
+    );
+    println!(
+        "{} of {} to download",
+        DecimalBytes(plan.total_remaining()),
+        DecimalBytes(plan.total_size())
+    );
+    println!();
+}
+
+fn print_csv(listings: &[(&String, Vec<RepoFile>)]) -> anyhow::Result<()> {
+    let with_model = listings.len() > 1;
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    let header = ["path", "size", "sha256", "type"];
+    if with_model {
+        writer.write_record(["model_id"].iter().chain(&header))?;
+    } else {
+        writer.write_record(header)?;
+    }
+    for (model_id, files) in listings {
+        for file in files {
+            let size = file.size.to_string();
+            let row = [file.path.as_str(), &size, &file.sha256, &file.r#type];
+            if with_model {
+                writer.write_record([model_id.as_str()].iter().chain(&row))?;
+            } else {
+                writer.write_record(row)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal, no is the default.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn parse_tls_pin(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((host, sha256)) if !host.is_empty() && !sha256.is_empty() => {
+            Ok((host.to_string(), sha256.to_string()))
+        }
+        _ => Err(format!("expected HOST=SHA256, got {}", s)),
+    }
+}
+
+/// Number of files of a local model and when the newest one was modified, without the manifest.
+fn scan_model_dir(dir: &Path) -> (usize, Option<SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (0, None);
+    };
+    let mut files = 0;
+    let mut newest = None;
+    for entry in entries.filter_map(Result::ok) {
+        if entry.file_name() == MANIFEST_FILE {
+            continue;
+        }
+        let Ok(metadata) = std::fs::metadata(entry.path()) else {
+            continue;
+        };
+        let (count, modified) = if metadata.is_dir() {
+            scan_model_dir(&entry.path())
+        } else {
+            (1, metadata.modified().ok())
+        };
+        files += count;
+        newest = newest.max(modified);
+    }
+    (files, newest)
+}
+
+/// The skipped files of the summaries, which JSON progress already reported.
+fn print_skipped(summaries: &[DownloadSummary], quiet: bool) {
+    if quiet {
+        return;
+    }
+    let skipped: Vec<_> = summaries
+        .iter()
+        .flat_map(|s| s.skipped.iter().map(move |f| (&s.model_id, f)))
+        .collect();
+    if skipped.is_empty() {
+        return;
+    }
+    println!();
+    println!("Skipped {} files:", skipped.len());
+    for (model_id, file) in skipped {
+        println!("  {}/{}: {}", model_id, file.path, file.reason);
+    }
+    println!();
+}
+
+fn init_logging(log_level: Option<&str>, quiet: bool) -> anyhow::Result<()> {
+    let filter = match log_level {
+        Some(level) => Some(
+            EnvFilter::try_new(level)
+                .map_err(|e| anyhow::anyhow!("Invalid --log-level {:?}: {}", level, e))?,
+        ),
+        None => EnvFilter::try_from_default_env().ok(),
+    };
+    // Timestamps and targets only help when diagnosing, the banners of the commands and plain
+    // warnings read better without them
+    let verbose = filter.as_ref().is_some_and(|filter| {
+        filter
+            .max_level_hint()
+            .is_none_or(|level| level > LevelFilter::WARN)
+    });
+    let filter = filter.unwrap_or_else(|| {
+        if quiet {
+            EnvFilter::new("warn")
+        } else {
+            EnvFilter::new("warn,modelscope_ng=info")
+        }
+    });
+    let logger = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(verbose);
+    if verbose {
+        logger.init();
+    } else {
+        logger.without_time().init();
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let json = args.progress == ProgressFormat::Json;
+    init_logging(args.log_level.as_deref(), args.quiet || json)?;
+    let result = run(args).await;
+    // The final status of the JSON events, the error still goes to stderr
+    if json && let Err(e) = &result {
+        let event = serde_json::json!({ "event": "failed", "error": format!("{:#}", e) });
+        println!("{}", event);
+    }
+    result
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    let progress = Progress::new(&args);
+    // Only the output of the command, no banners and reports
+    let quiet = args.quiet || args.progress == ProgressFormat::Json;
+    let defaults = DownloadOptions {
+        endpoint: args.endpoint.clone(),
+        proxy: args.proxy.clone(),
+        ca_certs: args.ca_cert.clone(),
+        tls_backend: args.tls_backend,
+        danger_accept_invalid_certs: args.insecure,
+        quiet,
+        hub: match (args.hub, &args.endpoint) {
+            (Hub::Modelscope, _) => None,
+            (Hub::Huggingface, None) => Some(Arc::new(HuggingFaceHub::new())),
+            (Hub::Huggingface, Some(endpoint)) => {
+                Some(Arc::new(HuggingFaceHub::new().with_endpoint(endpoint)))
+            }
+        },
+        ..DownloadOptions::default()
+    };
+    let defaults = match &args.profile {
+        // Logging in creates the profile, it doesn't have to exist yet
+        Some(name) if !matches!(args.command, SubCommand::Login { .. }) => {
+            Profile::load(name)?.apply(defaults)
+        }
+        _ => defaults,
+    };
+    // Flags and the profile take precedence over the config file. It is only read by the
+    // commands that use it, so a broken one doesn't get in the way of logout or completions.
+    let with_config = |options: DownloadOptions| Config::load().map(|config| config.apply(options));
+    // Resolved when used, like the config it comes from
+    let resolve_save_dir = |dir: Option<PathBuf>| dir.map_or_else(ModelScope::default_save_dir, Ok);
     match args.command {
-        SubCommand::Download { model_id, save_dir } => {
-            ModelScope::download(&model_id, &save_dir).await?;
+        SubCommand::Download {
+            model_id,
+            save_dir,
+            revision,
+            dry_run,
+            format,
+            jobs,
+            connections,
+            verify,
+            deterministic,
+            on_oversize,
+            api_interval,
+            locked,
+            update_lock,
+            lockfile,
+            skip_missing,
+            on_checksum_mismatch,
+            retries,
+            retry_deadline,
+            modified_since,
+            tls_pin,
+            git_compatible,
+            webhook,
+            subdir,
+            no_recursive,
+            prefer_safetensors,
+            regex,
+            include,
+            exclude,
+            weights_dir,
+            link_from,
+            endpoint_mirror,
+            layout,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            if dry_run {
+                let options = with_config(DownloadOptions {
+                    revision,
+                    subdir,
+                    recursive: !no_recursive,
+                    prefer_safetensors,
+                    regex_filter: regex,
+                    include,
+                    exclude,
+                    git_compatible,
+                    weights_dir,
+                    layout,
+                    ..defaults.clone()
+                })?;
+                if format == ListFormat::Plan {
+                    for model_id in &model_id {
+                        let plan = ModelScope::plan_download(model_id, &save_dir, &options).await?;
+                        print_plan(&plan);
+                    }
+                    return Ok(());
+                }
+                let mut listings = vec![];
+                for model_id in &model_id {
+                    let files = ModelScope::list_files_with_options(model_id, &options).await?;
+                    listings.push((model_id, files));
+                }
+                match format {
+                    ListFormat::Csv => print_csv(&listings)?,
+                    _ => {
+                        for (model_id, files) in &listings {
+                            println!();
+                            print!("{}", render_tree(model_id, files));
+                            println!();
+                        }
+                    }
+                }
+            } else {
+                let options = with_config(DownloadOptions {
+                    revision,
+                    verify,
+                    deterministic,
+                    oversize_policy: on_oversize,
+                    api_min_interval: api_interval.map(Into::into),
+                    lock: if locked {
+                        Some(LockMode::Locked(lockfile))
+                    } else if update_lock {
+                        Some(LockMode::Update(lockfile))
+                    } else {
+                        None
+                    },
+                    skip_missing,
+                    checksum_failure_mode: on_checksum_mismatch,
+                    network_retries: retries,
+                    retry_deadline: retry_deadline.map(Into::into),
+                    modified_since: modified_since.map(Into::into),
+                    tls_pins: tls_pin.into_iter().fold(
+                        HashMap::new(),
+                        |mut pins, (host, sha256)| {
+                            pins.entry(host).or_insert_with(Vec::new).push(sha256);
+                            pins
+                        },
+                    ),
+                    git_compatible,
+                    webhook: webhook.map(Webhook::new),
+                    subdir,
+                    recursive: !no_recursive,
+                    prefer_safetensors,
+                    regex_filter: regex,
+                    include,
+                    exclude,
+                    weights_dir,
+                    link_from,
+                    layout,
+                    endpoints: endpoint_mirror,
+                    max_open_files: jobs,
+                    connections,
+                    ..defaults.clone()
+                })?;
+                let model_ids: Vec<&str> = model_id.iter().map(String::as_str).collect();
+                let summaries =
+                    ModelScope::download_many(&model_ids, &save_dir, options, progress.clone())
+                        .await?;
+                print_skipped(&summaries, quiet);
+            }
         }
         SubCommand::DownloadFile {
             model_id,
             file_path,
             save_dir,
+            revision,
+            connections,
+            verify,
+            skip_missing,
+            output,
+            on_checksum_mismatch,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            let options = with_config(DownloadOptions {
+                revision,
+                connections,
+                verify,
+                skip_missing,
+                checksum_failure_mode: on_checksum_mismatch,
+                ..defaults
+            })?;
+            let summary = ModelScope::download_single_file_as(
+                &model_id,
+                &file_path,
+                &save_dir,
+                output.as_deref(),
+                options,
+                progress.clone(),
+            )
+            .await?;
+            print_skipped(&[summary], quiet);
+        }
+        SubCommand::DownloadDataset {
+            dataset_id,
+            save_dir,
+            revision,
+            jobs,
+            verify,
+            subdir,
+            include,
+            exclude,
+        } => {
+            let save_dir = save_dir.map_or_else(ModelScope::default_dataset_dir, Ok)?;
+            let options = with_config(DownloadOptions {
+                revision,
+                max_open_files: jobs,
+                verify,
+                subdir,
+                include,
+                exclude,
+                ..defaults.clone()
+            })?;
+            let summary =
+                ModelScope::download_dataset(&dataset_id, &save_dir, options, progress.clone())
+                    .await?;
+            print_skipped(&[summary], quiet);
+        }
+        SubCommand::Resume {
+            model_id,
+            save_dir,
+            verify,
+            git_compatible,
+            jobs,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            let options = with_config(DownloadOptions {
+                verify,
+                git_compatible,
+                max_open_files: jobs,
+                ..defaults.clone()
+            })?;
+            let summary =
+                ModelScope::resume(&model_id, &save_dir, options, progress.clone()).await?;
+            print_skipped(&[summary], quiet);
+        }
+        SubCommand::Repair {
+            model_id,
+            save_dir,
+            git_compatible,
+            verify_concurrency,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            let options = with_config(DownloadOptions {
+                git_compatible,
+                verify_concurrency,
+                ..defaults
+            })?;
+            let summary =
+                ModelScope::repair(&model_id, &save_dir, options, progress.clone()).await?;
+            print_skipped(&[summary], quiet);
+        }
+        SubCommand::Verify {
+            model_id,
+            save_dir,
+            revision,
+            git_compatible,
+            verify_concurrency,
+            json,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            let options = with_config(DownloadOptions {
+                revision,
+                git_compatible,
+                verify_concurrency,
+                ..defaults
+            })?;
+            let report = ModelScope::verify_model(&model_id, &save_dir, options).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!();
+                for path in &report.missing {
+                    println!("missing:   {}", path);
+                }
+                for file in &report.corrupted {
+                    println!("corrupted: {} ({})", file.path, file.reason);
+                }
+                for path in &report.extra {
+                    println!("extra:     {}", path);
+                }
+                println!(
+                    "{} ok, {} missing, {} corrupted, {} extra",
+                    report.ok.len(),
+                    report.missing.len(),
+                    report.corrupted.len(),
+                    report.extra.len()
+                );
+                println!();
+            }
+            if !report.is_intact() {
+                std::process::exit(1);
+            }
+        }
+        SubCommand::Sync {
+            model_id,
+            save_dir,
+            revision,
+            verify,
+            jobs,
+        } => {
+            let save_dir = resolve_save_dir(save_dir)?;
+            let options = with_config(DownloadOptions {
+                revision,
+                verify,
+                max_open_files: jobs,
+                ..defaults.clone()
+            })?;
+            let report = ModelScope::sync(&model_id, &save_dir, options, progress.clone()).await?;
+            if let Progress::Json(_) = progress {
+                let event = serde_json::json!({ "event": "sync_complete", "report": report });
+                println!("{}", event);
+                return Ok(());
+            }
+            if args.quiet {
+                return Ok(());
+            }
+            print_skipped(std::slice::from_ref(&report.summary), quiet);
+            println!();
+            for (mark, paths) in [
+                ("+", &report.added),
+                ("~", &report.updated),
+                ("-", &report.deleted),
+            ] {
+                for path in paths {
+                    println!("{} {}", mark, path);
+                }
+            }
+            println!(
+                "{} added, {} updated, {} unchanged, {} deleted",
+                report.added.len(),
+                report.updated.len(),
+                report.unchanged.len(),
+                report.deleted.len()
+            );
+            println!();
+        }
+        SubCommand::Info {
+            model_id,
+            local,
+            git_compatible,
+            save_dir,
+        } => {
+            let options = with_config(DownloadOptions {
+                git_compatible,
+                ..defaults
+            })?;
+            if !local {
+                let info = ModelScope::model_info_with_options(&model_id, &options).await?;
+                println!();
+                println!("Model:           {}", info.model_id);
+                if !info.description.is_empty() {
+                    println!("Description:     {}", info.description);
+                }
+                if let Some(license) = &info.license {
+                    println!("License:         {}", license);
+                }
+                if !info.tasks.is_empty() {
+                    println!("Tasks:           {}", info.tasks.join(", "));
+                }
+                println!("Latest revision: {}", info.latest_revision);
+                println!("Files:           {}", info.file_count);
+                println!("Total size:      {}", DecimalBytes(info.total_size));
+                println!("Downloads:       {}", info.downloads);
+                let auth = if info.requires_auth { "yes" } else { "no" };
+                println!("Requires login:  {}", auth);
+                println!();
+                return Ok(());
+            }
+            let save_dir = resolve_save_dir(save_dir)?;
+            let info = ModelScope::local_info_with_options(&model_id, save_dir, &options).await?;
+            let complete = info.files.iter().filter(|f| f.is_complete()).count();
+            println!();
+            println!("Model:      {}", info.model_id);
+            println!("Path:       {}", info.path.display());
+            println!("Revision:   {}", info.revision);
+            println!(
+                "Downloaded: {}",
+                humantime::format_rfc3339_seconds(info.downloaded_at)
+            );
+            println!("Total size: {}", DecimalBytes(info.total_size));
+            println!("Files:      {} / {} complete", complete, info.files.len());
+            println!();
+            for file in &info.files {
+                let status = if !file.is_complete() {
+                    "incomplete"
+                } else if file.verified {
+                    "verified"
+                } else {
+                    "complete"
+                };
+                println!(
+                    "  {:<50} {:>12} {}",
+                    file.path,
+                    DecimalBytes(file.size).to_string(),
+                    status
+                );
+            }
+            println!();
+        }
+        SubCommand::LsFiles {
+            model_id,
+            revision,
+            subdir,
+            tree,
+            json,
+        } => {
+            let options = with_config(DownloadOptions {
+                revision,
+                subdir,
+                ..defaults
+            })?;
+            let files = ModelScope::list_files_with_options(&model_id, &options).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&files)?);
+            } else if tree {
+                print!("{}", render_tree(&model_id, &files));
+            } else {
+                for file in &files {
+                    let size = if file.is_file() {
+                        file.size.to_string()
+                    } else {
+                        "-".to_string()
+                    };
+                    println!(
+                        "{:<60} {:>12} {:<4} {}",
+                        file.path, size, file.r#type, file.sha256
+                    );
+                }
+            }
+        }
+        SubCommand::Cat {
+            model_id,
+            file,
+            revision,
+        } => {
+            use std::io::Write;
+
+            let options = with_config(DownloadOptions {
+                revision,
+                ..defaults
+            })?;
+            let stream = ModelScope::stream_file(&model_id, &file, &options).await?;
+            let mut stream = std::pin::pin!(stream);
+            let mut stdout = std::io::stdout().lock();
+            while let Some(chunk) = stream.next().await {
+                match stdout.write_all(&chunk?) {
+                    // The reader went away, like `head`, nothing more to print
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+                    result => result?,
+                }
+            }
+            stdout.flush()?;
+        }
+        SubCommand::Upload {
+            model_id,
+            path,
+            revision,
+            message,
+            jobs,
+        } => {
+            let options = with_config(DownloadOptions {
+                revision,
+                max_open_files: jobs,
+                ..defaults.clone()
+            })?;
+            let summary =
+                ModelScope::upload(&model_id, &path, &message, &options, progress.clone()).await?;
+            if let Progress::Json(_) = progress {
+                let event = serde_json::json!({ "event": "upload_complete", "summary": summary });
+                println!("{}", event);
+                return Ok(());
+            }
+            if args.quiet {
+                return Ok(());
+            }
+            println!();
+            println!(
+                "Uploaded {} files ({}) to {}",
+                summary.uploaded.len(),
+                DecimalBytes(summary.bytes),
+                model_id
+            );
+            if !summary.existing.is_empty() {
+                println!(
+                    "{} files were already on the hub and only committed",
+                    summary.existing.len()
+                );
+            }
+        }
+        SubCommand::CreateRepo {
+            model_id,
+            visibility,
+            license,
+            chinese_name,
         } => {
-            ModelScope::download_single_file(&model_id, &file_path, &save_dir).await?;
+            let url = ModelScope::create_model_with_options(
+                &model_id,
+                visibility,
+                &license,
+                chinese_name.as_deref(),
+                &with_config(defaults)?,
+            )
+            .await?;
+            println!("Created {}", url);
+        }
+        SubCommand::Search { query, limit, json } => {
+            let options = with_config(defaults)?;
+            let models = ModelScope::search_with_options(&query, limit, &options).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&models)?);
+                return Ok(());
+            }
+            if models.is_empty() {
+                println!("No models found.");
+                return Ok(());
+            }
+            println!();
+            for model in &models {
+                let updated = model
+                    .last_updated
+                    .map(|secs| {
+                        let time = UNIX_EPOCH + Duration::from_secs(secs);
+                        humantime::format_rfc3339_seconds(time).to_string()[..10].to_string()
+                    })
+                    .unwrap_or_default();
+                println!(
+                    "{:<50} {:>10} {:<10} {}",
+                    model.model_id,
+                    model.downloads,
+                    updated,
+                    model.tasks.join(", ")
+                );
+            }
+            println!();
         }
         SubCommand::Login { token } => {
-            ModelScope::login(&token).await?;
+            // --endpoint is saved with the profile
+            let saved = args.profile.as_deref().and_then(|p| Profile::load(p).ok());
+            let options = with_config(DownloadOptions {
+                endpoint: args.endpoint.or_else(|| saved.and_then(|p| p.endpoint)),
+                profile: args.profile,
+                ..defaults
+            })?;
+            ModelScope::login_with_options(&token, &options).await?;
         }
         SubCommand::Logout => {
-            ModelScope::logout().await?;
+            ModelScope::logout_with_options(&defaults).await?;
         }
-        SubCommand::List => {
+        SubCommand::Whoami => {
+            let options = with_config(defaults)?;
+            match ModelScope::current_user_with_options(&options).await? {
+                Some(user) => {
+                    println!("{}", user.username);
+                    if !user.organizations.is_empty() {
+                        println!("Organizations: {}", user.organizations.join(", "));
+                    }
+                }
+                None => {
+                    anyhow::bail!("Not logged in, or the login expired. Use login with your token")
+                }
+            }
+        }
+        SubCommand::List { json } => {
             let models = ModelScope::list().await?;
+            if json {
+                let models: Vec<_> = models
+                    .iter()
+                    .map(|(model_id, path)| {
+                        // A linked model dir is counted where it points to
+                        let dir =
+                            std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+                        let (files, modified) = scan_model_dir(&dir);
+                        serde_json::json!({
+                            "model_id": model_id,
+                            "path": path,
+                            "size": modelscope_ng::disk_usage(&dir),
+                            "files": files,
+                            "last_modified": modified
+                                .map(|t| humantime::format_rfc3339_seconds(t).to_string()),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&models)?);
+                return Ok(());
+            }
             if models.is_empty() {
                 println!();
                 println!("No local models found.");
@@ -88,6 +1338,127 @@ async fn main() -> anyhow::Result<()> {
                 println!();
             }
         }
+        SubCommand::Du { sort } => {
+            let mut models: Vec<(String, String, u64)> = ModelScope::list()
+                .await?
+                .into_iter()
+                .map(|(model_id, path)| {
+                    // A linked model dir is counted where it points to
+                    let dir = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone().into());
+                    let bytes = modelscope_ng::disk_usage(&dir);
+                    (model_id, path, bytes)
+                })
+                .collect();
+            match sort {
+                DuSort::Size => models.sort_by_key(|m| std::cmp::Reverse(m.2)),
+                DuSort::Name => models.sort_by(|a, b| a.0.cmp(&b.0)),
+            }
+            println!();
+            for (model_id, path, bytes) in &models {
+                println!(
+                    "{:>12}  {:<50} {}",
+                    DecimalBytes(*bytes).to_string(),
+                    model_id,
+                    path
+                );
+            }
+            let total: u64 = models.iter().map(|m| m.2).sum();
+            println!(
+                "{:>12}  total of {} models",
+                DecimalBytes(total).to_string(),
+                models.len()
+            );
+            println!();
+        }
+        SubCommand::Gc { resume, yes } => {
+            let partials = ModelScope::find_partial_downloads()?;
+            if partials.is_empty() {
+                println!("No partial downloads found.");
+                return Ok(());
+            }
+            for partial in &partials {
+                let size = partial
+                    .size
+                    .map(|size| DecimalBytes(size).to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{} ({} / {})",
+                    partial.path.display(),
+                    DecimalBytes(partial.downloaded),
+                    size
+                );
+            }
+            if resume {
+                let mut models = BTreeSet::new();
+                for partial in &partials {
+                    match (&partial.model_id, partial.save_dir()) {
+                        (Some(model_id), Some(save_dir)) => {
+                            models.insert((model_id.clone(), save_dir));
+                        }
+                        _ => eprintln!(
+                            "Warning: {} can't be resumed from its manifest, download it again",
+                            partial.path.display()
+                        ),
+                    }
+                }
+                let options = with_config(defaults)?;
+                for (model_id, save_dir) in models {
+                    let summary =
+                        ModelScope::resume(&model_id, &save_dir, options.clone(), progress.clone())
+                            .await?;
+                    print_skipped(&[summary], quiet);
+                }
+                return Ok(());
+            }
+            let total: u64 = partials.iter().map(|p| p.downloaded).sum();
+            let question = format!(
+                "Delete {} partial download(s), {}?",
+                partials.len(),
+                DecimalBytes(total)
+            );
+            if !yes && !confirm(&question)? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
+            for partial in &partials {
+                ModelScope::remove_partial_download(partial)?;
+            }
+            println!("Deleted {} partial download(s).", partials.len());
+        }
+        SubCommand::Rm {
+            model_id,
+            save_dir,
+            yes,
+        } => {
+            let mut dirs = ModelScope::find_local(&model_id)?;
+            if let Some(save_dir) = save_dir {
+                let save_dir = save_dir.canonicalize()?;
+                dirs.retain(|dir| dir.starts_with(&save_dir));
+            }
+            if dirs.is_empty() {
+                anyhow::bail!("Model {} was not found in the known save dirs", model_id);
+            }
+            for dir in &dirs {
+                println!(
+                    "{} ({})",
+                    dir.display(),
+                    DecimalBytes(modelscope_ng::disk_usage(dir))
+                );
+            }
+            if !yes && !confirm(&format!("Delete {} director(ies)?", dirs.len()))? {
+                println!("Nothing deleted.");
+                return Ok(());
+            }
+            for dir in &dirs {
+                ModelScope::remove_local(dir)?;
+                println!("Deleted {}", dir.display());
+            }
+        }
+        SubCommand::Completions { shell } => {
+            let mut command = Args::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+        }
     };
 
     Ok(())
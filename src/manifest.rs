@@ -0,0 +1,226 @@
+use crate::{ChunkHashes, RepoFile, RepoType, is_weight_file};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the manifest stored in every downloaded model directory
+pub const MANIFEST_FILE: &str = ".modelscope-manifest.json";
+
+/// Record of what was downloaded into a model directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub model_id: String,
+    pub revision: String,
+    /// Older manifests are all models
+    #[serde(default, skip_serializing_if = "RepoType::is_model")]
+    pub repo_type: RepoType,
+    /// Unix timestamp in seconds of the last download
+    pub downloaded_at: u64,
+    /// Where weight files were saved, when they are kept apart from the model directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weights_dir: Option<PathBuf>,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Whether the sha256 was checked after the last download
+    pub verified: bool,
+    /// Whether the download finished, which the local size alone can't tell for empty files
+    #[serde(default)]
+    pub complete: bool,
+    /// Chunk digests of the verified download, lets `repair` fetch only corrupt ranges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_hashes: Option<ChunkHashes>,
+}
+
+impl Manifest {
+    pub fn new(model_id: &str, revision: &str) -> Self {
+        Self {
+            model_id: model_id.to_string(),
+            revision: revision.to_string(),
+            repo_type: RepoType::Model,
+            downloaded_at: now(),
+            weights_dir: None,
+            files: vec![],
+        }
+    }
+
+    /// Load the manifest of a model directory, `None` if the model has no manifest.
+    pub fn load(model_dir: &Path) -> anyhow::Result<Option<Self>> {
+        let f = model_dir.join(MANIFEST_FILE);
+        if !f.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(f)?)?))
+    }
+
+    pub fn save(&self, model_dir: &Path) -> anyhow::Result<()> {
+        tracing::trace!(dir = %model_dir.display(), files = self.files.len(), "saving manifest");
+        fs::write(
+            model_dir.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// Insert or replace the entry for a repository file.
+    pub fn upsert(&mut self, repo_file: &RepoFile, verified: bool) {
+        let entry = ManifestFile {
+            path: repo_file.path.clone(),
+            size: repo_file.size,
+            sha256: repo_file.sha256.clone(),
+            verified,
+            complete: false,
+            chunk_hashes: repo_file.chunk_hashes.clone(),
+        };
+        match self.files.iter_mut().find(|f| f.path == repo_file.path) {
+            Some(f) => *f = entry,
+            None => self.files.push(entry),
+        }
+    }
+
+    /// Record that a file finished downloading, with its chunk digests if it was verified.
+    pub(crate) fn mark_complete(
+        &mut self,
+        path: &str,
+        verified: bool,
+        chunk_hashes: Option<&ChunkHashes>,
+    ) {
+        if let Some(f) = self.files.iter_mut().find(|f| f.path == path) {
+            f.complete = true;
+            f.verified = verified;
+            if let Some(chunk_hashes) = chunk_hashes {
+                f.chunk_hashes = Some(chunk_hashes.clone());
+            }
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&ManifestFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// The chunk digests recorded for this version of a repository file.
+    pub(crate) fn chunk_hashes(&self, repo_file: &RepoFile) -> Option<ChunkHashes> {
+        self.get(&repo_file.path)
+            .filter(|f| f.sha256 == repo_file.sha256 && f.size == repo_file.size)
+            .and_then(|f| f.chunk_hashes.clone())
+    }
+
+    /// Where a repository file is saved on disk.
+    pub fn local_path(&self, model_dir: &Path, path: &str) -> PathBuf {
+        match &self.weights_dir {
+            Some(weights_dir) if is_weight_file(path) => weights_dir.join(path),
+            _ => model_dir.join(path),
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    pub fn downloaded_at(&self) -> SystemTime {
+        UNIX_EPOCH + std::time::Duration::from_secs(self.downloaded_at)
+    }
+
+    pub(crate) fn touch(&mut self) {
+        self.downloaded_at = now();
+    }
+}
+
+impl ManifestFile {
+    /// Rebuild the repository entry, for downloading without fetching the listing.
+    pub(crate) fn to_repo_file(&self) -> RepoFile {
+        RepoFile {
+            name: self.path.rsplit('/').next().unwrap_or_default().to_string(),
+            path: self.path.clone(),
+            size: self.size,
+            sha256: self.sha256.clone(),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: self.chunk_hashes.clone(),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// What is actually on disk for a downloaded model, see [`ModelScope::local_info`].
+///
+/// [`ModelScope::local_info`]: crate::ModelScope::local_info
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModelInfo {
+    pub model_id: String,
+    pub path: PathBuf,
+    pub revision: String,
+    pub downloaded_at: SystemTime,
+    /// Expected size of all files in bytes
+    pub total_size: u64,
+    pub files: Vec<LocalFileInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalFileInfo {
+    pub path: String,
+    /// Expected size in bytes
+    pub size: u64,
+    /// Size of the local file, 0 if it is missing
+    pub local_size: u64,
+    pub sha256: String,
+    pub verified: bool,
+    /// Whether the manifest records the download as finished
+    pub complete: bool,
+}
+
+impl LocalFileInfo {
+    /// An empty local file is only complete if its download finished, otherwise it may have
+    /// been created right before an interruption.
+    pub fn is_complete(&self) -> bool {
+        is_finished(self.local_size, self.size, self.complete)
+    }
+}
+
+/// Whether a local file of `local_size` bytes is a finished download of `size` bytes. The size
+/// can't tell an empty file that was created right before a crash from a downloaded one, so
+/// empty files need the completion `recorded` in the manifest.
+pub(crate) fn is_finished(local_size: u64, size: u64, recorded: bool) -> bool {
+    local_size == size && (size > 0 || recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_hashes_are_only_reused_for_the_same_version() {
+        let mut file = RepoFile {
+            name: "model.bin".to_string(),
+            path: "model.bin".to_string(),
+            size: 100,
+            sha256: "ab".repeat(32),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: None,
+        };
+        let chunk_hashes = ChunkHashes {
+            chunk_size: 64,
+            sha256: vec!["cd".repeat(32), "ef".repeat(32)],
+        };
+        let mut manifest = Manifest::new("vendor/model", "master");
+        manifest.upsert(&file, true);
+        manifest.mark_complete(&file.path, true, Some(&chunk_hashes));
+        assert_eq!(manifest.chunk_hashes(&file), Some(chunk_hashes));
+
+        file.sha256 = "12".repeat(32);
+        assert_eq!(manifest.chunk_hashes(&file), None);
+    }
+}
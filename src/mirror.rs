@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use tokio::io::AsyncWrite;
+
+/// A secondary destination that receives a copy of every downloaded file.
+///
+/// This is useful for maintaining an internal mirror: the bytes fetched from
+/// ModelScope are written to local disk and to the writer returned by the sink
+/// in the same pass, e.g. an upload stream to an S3-compatible store.
+#[async_trait]
+pub trait MirrorSink: Send + Sync {
+    /// Open a writer for a single repository file.
+    ///
+    /// The writer always receives the complete file from the first byte, even
+    /// when the local copy is resumed or already complete. It is shut down once
+    /// the file has been written. `size` is 0 when the listing does not know the size.
+    async fn open(
+        &self,
+        model_id: &str,
+        path: &str,
+        size: u64,
+    ) -> anyhow::Result<Box<dyn AsyncWrite + Send + Unpin>>;
+
+    /// Called instead of the shutdown when the writer of a file is dropped before the file
+    /// was complete, e.g. because the download or its verification failed. A sink that stores
+    /// objects as they are written should delete the partial object here. Does nothing by
+    /// default.
+    async fn abort(&self, _model_id: &str, _path: &str) {}
+}
@@ -0,0 +1,113 @@
+use crate::{DownloadOptions, ModelScope, RepoStats, Visibility, api};
+use serde::{Deserialize, Serialize};
+
+const MODEL_URL: &str = "<endpoint>/api/v1/models/<model_id>";
+const REVISIONS_URL: &str = "<endpoint>/api/v1/models/<model_id>/revisions";
+
+/// Metadata of a model on the hub, see [`ModelScope::model_info`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub model_id: String,
+    pub description: String,
+    pub license: Option<String>,
+    /// Task tags, like `text-generation`
+    pub tasks: Vec<String>,
+    pub downloads: u64,
+    /// Unix timestamp in seconds of the last update
+    pub last_updated: Option<u64>,
+    /// Newest tag, or the default branch when the model has no tags
+    pub latest_revision: String,
+    /// Files and their total size at the revision of the options, `master` by default
+    pub file_count: usize,
+    pub total_size: u64,
+    /// Whether the model is private or internal, downloading it needs a login
+    pub requires_auth: bool,
+}
+
+#[derive(Deserialize)]
+struct Model {
+    #[serde(rename = "Description", default)]
+    description: String,
+    #[serde(rename = "License", default)]
+    license: Option<String>,
+    #[serde(rename = "Tasks", default)]
+    tasks: Vec<api::Task>,
+    #[serde(rename = "Downloads", default)]
+    downloads: u64,
+    #[serde(rename = "LastUpdatedTime", default)]
+    last_updated: Option<u64>,
+    #[serde(rename = "Visibility", default)]
+    visibility: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Revisions {
+    #[serde(rename = "RevisionMap")]
+    revision_map: RevisionMap,
+}
+
+#[derive(Deserialize)]
+struct RevisionMap {
+    #[serde(rename = "Tags", default)]
+    tags: Option<Vec<Revision>>,
+}
+
+#[derive(Deserialize)]
+struct Revision {
+    #[serde(rename = "Revision")]
+    revision: String,
+    #[serde(rename = "CreatedAt", default)]
+    created_at: u64,
+}
+
+impl ModelScope {
+    /// Fetch the metadata of a model from the hub, with the file count and size of its listing.
+    pub async fn model_info(model_id: &str) -> anyhow::Result<ModelInfo> {
+        Self::model_info_with_options(model_id, &DownloadOptions::default()).await
+    }
+
+    pub async fn model_info_with_options(
+        model_id: &str,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<ModelInfo> {
+        let (options, model_id) = options.for_model(model_id);
+        let options = options.as_ref();
+        options.ensure_modelscope("get model information")?;
+        let client = Self::get_client_with(options).await?;
+        let url = |template: &str| {
+            template
+                .replace("<endpoint>", options.endpoint())
+                .replace("<model_id>", model_id)
+        };
+        let action = format!("get information of model {}", model_id);
+        let (model, revisions, files) = tokio::try_join!(
+            api::send::<Model>(client.get(url(MODEL_URL)), options, &action),
+            api::send::<Revisions>(client.get(url(REVISIONS_URL)), options, &action),
+            Self::fetch_repo_files(&client, model_id, options),
+        )?;
+        let model = model.ok_or_else(|| anyhow::anyhow!("Failed to {}: no data", action))?;
+        let stats = RepoStats::from_files(&files);
+
+        let latest_tag = revisions
+            .and_then(|r| r.revision_map.tags)
+            .unwrap_or_default()
+            .into_iter()
+            .max_by_key(|tag| tag.created_at);
+        Ok(ModelInfo {
+            model_id: model_id.to_string(),
+            description: model.description,
+            license: model.license.filter(|l| !l.is_empty()),
+            tasks: model.tasks.into_iter().map(|t| t.name).collect(),
+            downloads: model.downloads,
+            last_updated: model.last_updated,
+            latest_revision: latest_tag
+                .map(|tag| tag.revision)
+                .unwrap_or_else(|| crate::DEFAULT_REVISION.to_string()),
+            file_count: stats.blob_count,
+            total_size: stats.total_bytes,
+            requires_auth: model
+                .visibility
+                .is_some_and(|v| v != Visibility::Public.code()),
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+
+/// Name of the network filesystem `path` is on, `None` for local disks or when unknown.
+///
+/// Best effort: only Linux and macOS are detected.
+pub(crate) fn network_fs(path: &Path) -> Option<&'static str> {
+    imp::network_fs(path)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// `f_type` magic numbers, see statfs(2)
+    const NETWORK_FS: [(i64, &str); 9] = [
+        (0x6969, "nfs"),
+        (0x517b, "smb"),
+        (0xfe53_4d42, "smb2"),
+        (0xff53_4d42, "cifs"),
+        (0x00c3_6400, "ceph"),
+        (0x5346_414f, "afs"),
+        (0x0102_1997, "9p"),
+        (0x0bd0_0bd0, "lustre"),
+        (0x0116_1970, "gfs2"),
+    ];
+
+    pub(super) fn network_fs(path: &Path) -> Option<&'static str> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        // SAFETY: path is NUL terminated and statfs only writes to the struct we pass in
+        if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        // The width and sign of f_type differ between libcs
+        #[allow(clippy::unnecessary_cast)]
+        let fs_type = stat.f_type as i64 & 0xffff_ffff;
+        NETWORK_FS
+            .iter()
+            .find(|(magic, _)| *magic == fs_type)
+            .map(|(_, name)| *name)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const NETWORK_FS: [&str; 5] = ["nfs", "smbfs", "afpfs", "webdav", "cifs"];
+
+    pub(super) fn network_fs(path: &Path) -> Option<&'static str> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        // SAFETY: path is NUL terminated and statfs only writes to the struct we pass in
+        if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        // SAFETY: the kernel NUL terminates f_fstypename
+        let name = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+        let name = name.to_str().ok()?;
+        NETWORK_FS.into_iter().find(|fs| *fs == name)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod imp {
+    use std::path::Path;
+
+    pub(super) fn network_fs(_path: &Path) -> Option<&'static str> {
+        None
+    }
+}
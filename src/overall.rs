@@ -0,0 +1,137 @@
+use crate::{ProgressCallback, RepoFile};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex};
+
+/// Bytes done across all files of a download, weighted by size rather than file count.
+///
+/// Models downloaded together share one, files are keyed by `<model_id>/<path>`.
+#[derive(Default)]
+pub(crate) struct OverallProgress {
+    /// Total size, sum of the done bytes and the bytes done per file
+    state: Mutex<(u64, u64, HashMap<String, u64>)>,
+}
+
+impl OverallProgress {
+    /// Add the files of a model to the total, files that were added before are counted once.
+    pub(crate) fn add(&self, model_id: &str, files: &[RepoFile]) {
+        let mut state = self.state.lock().unwrap();
+        let (total, _, done) = &mut *state;
+        for file in files {
+            let key = format!("{}/{}", model_id, file.path);
+            if let Entry::Vacant(entry) = done.entry(key) {
+                entry.insert(0);
+                *total += file.size;
+            }
+        }
+    }
+
+    /// Record the bytes done for a file and return the new overall progress.
+    pub(crate) fn update(&self, key: &str, bytes: u64) -> (u64, u64) {
+        let mut state = self.state.lock().unwrap();
+        let (total, sum, files) = &mut *state;
+        let previous = files.insert(key.to_string(), bytes).unwrap_or(0);
+        *sum = *sum + bytes - previous;
+        ((*sum).min(*total), *total)
+    }
+
+    /// Count the whole file as done, for complete and skipped files.
+    pub(crate) async fn finish<C: ProgressCallback>(
+        &self,
+        model_id: &str,
+        file: &RepoFile,
+        callback: &C,
+    ) {
+        let key = format!("{}/{}", model_id, file.path);
+        let (downloaded, total) = self.update(&key, file.size);
+        callback.on_overall_progress(downloaded, total).await;
+    }
+}
+
+/// Forwards to the user callback and reports the overall progress along with each file.
+///
+/// The user callback sees `name` as the file name, which is unique across models when several
+/// models share one display.
+#[derive(Clone)]
+pub(crate) struct TrackedCallback<C> {
+    inner: C,
+    model_id: String,
+    file: RepoFile,
+    name: String,
+    overall: Arc<OverallProgress>,
+}
+
+impl<C: ProgressCallback> TrackedCallback<C> {
+    pub(crate) fn new(
+        inner: C,
+        model_id: &str,
+        file: RepoFile,
+        name: String,
+        overall: Arc<OverallProgress>,
+    ) -> Self {
+        Self {
+            inner,
+            model_id: model_id.to_string(),
+            file,
+            name,
+            overall,
+        }
+    }
+
+    pub(crate) async fn finish(&self) {
+        self.overall
+            .finish(&self.model_id, &self.file, &self.inner)
+            .await;
+    }
+}
+
+#[async_trait]
+impl<C: ProgressCallback> ProgressCallback for TrackedCallback<C> {
+    async fn on_file_start(&self, _file_name: &str, file_size: u64) {
+        self.inner.on_file_start(&self.name, file_size).await;
+    }
+
+    async fn on_file_progress(&self, _file_name: &str, downloaded: u64, total: u64) {
+        self.inner
+            .on_file_progress(&self.name, downloaded, total)
+            .await;
+        // Files of unknown size are not part of the overall total
+        if self.file.size > 0 {
+            let key = format!("{}/{}", self.model_id, self.file.path);
+            let (downloaded, total) = self.overall.update(&key, downloaded);
+            self.inner.on_overall_progress(downloaded, total).await;
+        }
+    }
+
+    async fn on_file_complete(&self, _file_name: &str) {
+        self.inner.on_file_complete(&self.name).await;
+    }
+
+    async fn on_file_error(&self, _file_name: &str, error: &str) {
+        self.inner.on_file_error(&self.name, error).await;
+    }
+
+    async fn on_file_skipped(&self, _file_name: &str, reason: &str) {
+        self.inner.on_file_skipped(&self.name, reason).await;
+    }
+
+    async fn on_file_retry(&self, _file_name: &str, attempt: u32, error: &str) {
+        self.inner.on_file_retry(&self.name, attempt, error).await;
+    }
+
+    async fn on_repo_file_start(&self, file: &RepoFile) {
+        if file.name == self.name {
+            return self.inner.on_repo_file_start(file).await;
+        }
+        let file = RepoFile {
+            name: self.name.clone(),
+            ..file.clone()
+        };
+        self.inner.on_repo_file_start(&file).await;
+    }
+
+    async fn on_overall_progress(&self, downloaded: u64, total: u64) {
+        self.inner.on_overall_progress(downloaded, total).await;
+    }
+}
@@ -0,0 +1,205 @@
+use crate::incomplete::{self, Part, Sidecar, incomplete_path};
+use crate::summary::FileOutcome;
+use crate::{DEFAULT_FLUSH_INTERVAL, ModelScope, ProgressCallback, RepoFile, StreamVerifier};
+use futures_util::StreamExt;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// Files smaller than this are not worth the extra connections
+pub(crate) const MIN_SPLIT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Whether a file is downloaded over several connections, see
+/// [`DownloadOptions::connections`](crate::DownloadOptions::connections).
+///
+/// A file already partly downloaded over one connection keeps resuming that way.
+pub(crate) fn should_split(repo_file: &RepoFile, file_path: &Path, connections: usize) -> bool {
+    connections > 1
+        && repo_file.size >= MIN_SPLIT_SIZE
+        && !file_path.exists()
+        && Sidecar::load(repo_file, file_path).is_none_or(|s| !s.ranges.is_empty())
+}
+
+/// The ranges of a new split download, one per connection.
+fn split(size: u64, connections: usize) -> Vec<Part> {
+    let step = size.div_ceil(connections as u64);
+    (0..size)
+        .step_by(step as usize)
+        .map(|start| Part {
+            start,
+            end: (start + step).min(size),
+            done: 0,
+        })
+        .collect()
+}
+
+impl ModelScope {
+    /// Download a file as byte ranges over several connections into `<file>.incomplete`, then
+    /// move it into place.
+    ///
+    /// Returns `None` without touching the disk when the server does not answer range requests,
+    /// the file is then downloaded over one connection.
+    pub(crate) async fn download_split<C: ProgressCallback>(
+        client: &reqwest::Client,
+        url: &str,
+        repo_file: &RepoFile,
+        file_path: &Path,
+        connections: usize,
+        options: &crate::DownloadOptions,
+        callback: &C,
+    ) -> anyhow::Result<Option<FileOutcome>> {
+        let name = &repo_file.name;
+        let incomplete = incomplete_path(file_path);
+        // The partial file is preallocated, anything shorter is not one of ours
+        let parts = Sidecar::load(repo_file, file_path).filter(|s| {
+            !s.ranges.is_empty() && fs::metadata(&incomplete).is_ok_and(|m| m.len() == s.size)
+        });
+        let resuming = parts.is_some();
+        let parts =
+            parts.unwrap_or_else(|| Sidecar::new(repo_file, split(repo_file.size, connections)));
+
+        let mut responses = vec![];
+        for (index, part) in parts.ranges.iter().enumerate() {
+            if part.next() >= part.end {
+                continue;
+            }
+            let request = client
+                .get(url)
+                .header("Range", format!("bytes={}-{}", part.next(), part.end - 1))
+                .send();
+            let resp = options.until_cancelled(request).await??;
+            if resp.status() == reqwest::StatusCode::OK && !resuming && responses.is_empty() {
+                return Ok(None);
+            }
+            if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                let error = crate::ModelScopeError::from_status(resp.status());
+                callback.on_file_error(name, &error.to_string()).await;
+                return Err(error.into());
+            }
+            responses.push((index, resp));
+        }
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !resuming {
+            fs::File::create(&incomplete)?.set_len(parts.size)?;
+            parts.save(file_path)?;
+        }
+
+        callback.on_repo_file_start(repo_file).await;
+        let downloaded = AtomicU64::new(parts.done());
+        callback
+            .on_file_progress(name, downloaded.load(Ordering::Relaxed), parts.size)
+            .await;
+
+        let flush_interval = options
+            .flush_interval
+            .unwrap_or(DEFAULT_FLUSH_INTERVAL)
+            .max(1);
+        let parts = Mutex::new(parts);
+        let ranges = responses.into_iter().map(|(index, resp)| {
+            let (parts, downloaded, incomplete) = (&parts, &downloaded, &incomplete);
+            let save = move |part: Part| {
+                let mut parts = parts.lock().unwrap();
+                parts.ranges[index] = part;
+                parts.save(file_path)
+            };
+            async move {
+                let mut part = parts.lock().unwrap().ranges[index];
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .open(incomplete)
+                    .await?;
+                file.seek(std::io::SeekFrom::Start(part.next())).await?;
+                let mut file = tokio::io::BufWriter::new(file);
+                let mut unsynced = 0;
+                let mut failure = None;
+                let mut stream = resp.bytes_stream();
+                while part.next() + unsynced < part.end {
+                    let chunk = match options.until_cancelled(stream.next()).await {
+                        Ok(Some(Ok(chunk))) => chunk,
+                        Ok(None) => break,
+                        Ok(Some(Err(e))) => {
+                            failure = Some(e.into());
+                            break;
+                        }
+                        Err(e) => {
+                            failure = Some(e);
+                            break;
+                        }
+                    };
+                    // A server sending more than asked must not overwrite the next range
+                    let room = (part.end - part.next() - unsynced) as usize;
+                    let chunk = chunk.slice(..chunk.len().min(room));
+                    file.write_all(&chunk).await?;
+                    unsynced += chunk.len() as u64;
+                    let total = downloaded.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                    callback
+                        .on_file_progress(name, total + chunk.len() as u64, repo_file.size)
+                        .await;
+                    if unsynced >= flush_interval {
+                        file.flush().await?;
+                        file.get_ref().sync_data().await?;
+                        part.done += unsynced;
+                        unsynced = 0;
+                        save(part)?;
+                    }
+                }
+                // Keep what was received even on failure, so the next attempt resumes after it
+                if unsynced > 0 {
+                    file.flush().await?;
+                    file.get_ref().sync_data().await?;
+                    part.done += unsynced;
+                    save(part)?;
+                }
+                if let Some(e) = failure {
+                    return Err(e);
+                }
+                if part.next() < part.end {
+                    anyhow::bail!(
+                        "connection closed at byte {} of range {}-{}",
+                        part.next(),
+                        part.start,
+                        part.end - 1
+                    );
+                }
+                anyhow::Ok(())
+            }
+        });
+        // Every range runs to its end, so a failing one doesn't lose the progress of the others
+        let results = futures_util::future::join_all(ranges).await;
+        if let Some(e) = results.into_iter().find_map(Result::err) {
+            callback.on_file_error(name, &e.to_string()).await;
+            return Err(e);
+        }
+
+        // Ranges arrive out of order, so the digest is checked once the file is whole, before
+        // it gets its real name
+        let mut chunk_hashes = None;
+        if options.verify {
+            let verifier = StreamVerifier::new(
+                &repo_file.path,
+                &repo_file.sha256,
+                repo_file.chunk_hashes.as_ref(),
+            );
+            let partial = incomplete_path(file_path);
+            let verifier = Self::verify_local_prefix(&partial, repo_file.size, verifier).await;
+            match verifier.and_then(StreamVerifier::finish) {
+                Ok(verified) => chunk_hashes = verified,
+                Err(e) => {
+                    // Finished ranges would be resumed as they are, the next download starts over
+                    Sidecar::new(repo_file, vec![]).save(file_path)?;
+                    callback.on_file_error(name, &e.to_string()).await;
+                    return Err(e);
+                }
+            }
+        }
+        incomplete::commit(file_path)?;
+
+        callback.on_file_complete(name).await;
+        Ok(Some(FileOutcome::Downloaded(repo_file.size, chunk_hashes)))
+    }
+}
@@ -0,0 +1,109 @@
+use crate::verify::to_hex;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Checks the platform trust store first, then requires the leaf certificate of pinned hosts
+/// to match one of the pinned sha256 fingerprints.
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: rustls_platform_verifier::Verifier,
+    /// Host name to lowercase hex sha256 fingerprints of accepted certificates
+    pins: HashMap<String, Vec<String>>,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+
+        let host = server_name.to_str();
+        if let Some(pins) = self.pins.get(host.as_ref()) {
+            let fingerprint = to_hex(&Sha256::digest(end_entity.as_ref()));
+            if !pins.contains(&fingerprint) {
+                return Err(rustls::Error::General(format!(
+                    "certificate of {} does not match any pinned fingerprint (got sha256 {})",
+                    host, fingerprint
+                )));
+            }
+        }
+        Ok(verified)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Build a rustls config that enforces certificate pins, for `ClientBuilder::tls_backend_preconfigured`.
+/// `extra_roots` are trusted besides the platform trust store.
+pub(crate) fn pinned_tls_config(
+    pins: &HashMap<String, Vec<String>>,
+    extra_roots: Vec<CertificateDer<'static>>,
+    http1_only: bool,
+) -> anyhow::Result<rustls::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let verifier = PinnedVerifier {
+        inner: rustls_platform_verifier::Verifier::new_with_extra_roots(
+            extra_roots,
+            provider.clone(),
+        )?,
+        pins: pins
+            .iter()
+            .map(|(host, fps)| {
+                let fps = fps
+                    .iter()
+                    .map(|fp| fp.replace(':', "").to_lowercase())
+                    .collect();
+                (host.to_lowercase(), fps)
+            })
+            .collect(),
+    };
+
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+
+    // reqwest leaves ALPN to preconfigured configs
+    config.alpn_protocols = if http1_only {
+        vec![b"http/1.1".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    Ok(config)
+}
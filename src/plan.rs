@@ -0,0 +1,179 @@
+use crate::incomplete::{self, Sidecar};
+use crate::manifest;
+use crate::{DownloadOptions, Manifest, ModelScope, RepoFile};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a download would do, see [`ModelScope::plan_download`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadPlan {
+    pub model_id: String,
+    pub model_dir: PathBuf,
+    pub files: Vec<PlannedFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    pub path: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    pub action: PlannedAction,
+    /// Bytes that would be downloaded
+    pub remaining: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlannedAction {
+    Download,
+    /// Continue a partial download of the same version of the file
+    Resume,
+    /// Already complete locally
+    Skip,
+}
+
+impl DownloadPlan {
+    /// Files with this action.
+    pub fn count(&self, action: PlannedAction) -> usize {
+        self.files.iter().filter(|f| f.action == action).count()
+    }
+
+    /// Bytes still to download for the files with this action.
+    pub fn remaining(&self, action: PlannedAction) -> u64 {
+        self.files
+            .iter()
+            .filter(|f| f.action == action)
+            .map(|f| f.remaining)
+            .sum()
+    }
+
+    /// Size of all files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Bytes the download would fetch.
+    pub fn total_remaining(&self) -> u64 {
+        self.files.iter().map(|f| f.remaining).sum()
+    }
+}
+
+impl ModelScope {
+    /// Plan a download without fetching any file: which files of the listing would be
+    /// downloaded, resumed from a partial download or skipped as already complete in
+    /// `save_dir`, and how many bytes that takes.
+    pub async fn plan_download(
+        model_id: &str,
+        save_dir: impl Into<PathBuf>,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<DownloadPlan> {
+        let save_dir = save_dir.into();
+        let files = Self::list_files_with_options(model_id, options).await?;
+        let (options, model_id) = options.for_model(model_id);
+        let model_dir = options.model_dir(&save_dir, model_id);
+
+        let previous = Manifest::load(&model_dir)?;
+        let mut manifest = Manifest::new(model_id, options.revision());
+        manifest.weights_dir = options.weights_model_dir(model_id);
+        let files = files
+            .iter()
+            .filter(|f| f.is_file())
+            .map(|file| {
+                let local_path = manifest.local_path(&model_dir, &file.path);
+                // Only the manifest knows whether an empty file finished downloading
+                let recorded = previous
+                    .as_ref()
+                    .and_then(|m| m.get(&file.path))
+                    .is_some_and(|f| f.complete);
+                plan_file(file, &local_path, recorded)
+            })
+            .collect();
+
+        Ok(DownloadPlan {
+            model_id: model_id.to_string(),
+            model_dir,
+            files,
+        })
+    }
+}
+
+/// The same checks the download makes before fetching a file.
+fn plan_file(file: &RepoFile, local_path: &Path, recorded: bool) -> PlannedFile {
+    let (action, remaining) = match fs::metadata(local_path) {
+        Ok(m) if manifest::is_finished(m.len(), file.size, recorded) => (PlannedAction::Skip, 0),
+        _ => match Sidecar::load(file, local_path) {
+            Some(sidecar) => {
+                let done = if sidecar.ranges.is_empty() {
+                    fs::metadata(incomplete::incomplete_path(local_path)).map_or(0, |m| m.len())
+                } else {
+                    sidecar.done()
+                };
+                (PlannedAction::Resume, file.size.saturating_sub(done))
+            }
+            None => (PlannedAction::Download, file.size),
+        },
+    };
+    PlannedFile {
+        path: file.path.clone(),
+        size: file.size,
+        action,
+        remaining,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_file(size: u64) -> RepoFile {
+        RepoFile {
+            name: "model.bin".to_string(),
+            path: "model.bin".to_string(),
+            size,
+            sha256: "ab".repeat(32),
+            r#type: "blob".to_string(),
+            committed_date: None,
+            chunk_hashes: None,
+        }
+    }
+
+    // A crash right after creating the file leaves it empty, with nothing downloaded yet
+    #[test]
+    fn empty_partial_file_is_resumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        let file = repo_file(100);
+        Sidecar::new(&file, vec![]).save(&path).unwrap();
+        fs::write(incomplete::incomplete_path(&path), b"").unwrap();
+
+        let planned = plan_file(&file, &path, false);
+        assert_eq!(planned.action, PlannedAction::Resume);
+        assert_eq!(planned.remaining, 100);
+    }
+
+    #[test]
+    fn empty_file_of_a_non_empty_one_is_downloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        fs::write(&path, b"").unwrap();
+
+        let planned = plan_file(&repo_file(100), &path, false);
+        assert_eq!(planned.action, PlannedAction::Download);
+        assert_eq!(planned.remaining, 100);
+    }
+
+    #[test]
+    fn empty_file_is_complete_when_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        fs::write(&path, b"").unwrap();
+
+        let recorded = plan_file(&repo_file(0), &path, true);
+        assert_eq!(recorded.action, PlannedAction::Skip);
+        assert_eq!(recorded.remaining, 0);
+        // Without the manifest it may be an interrupted download of an empty file
+        let unrecorded = plan_file(&repo_file(0), &path, false);
+        assert_eq!(unrecorded.action, PlannedAction::Download);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{Dirs, DownloadOptions};
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const PROFILES_DIR: &str = "profiles";
+
+/// A named account and endpoint, e.g. a personal login next to an enterprise deployment.
+///
+/// Each profile keeps its own login cookies, created with `login --profile <name>`, and may set
+/// the endpoint and an access token. Saved as `~/.modelscope/config/profiles/<name>.json`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> anyhow::Result<Self> {
+        check_name(name)?;
+        Ok(Self {
+            name: name.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Load a saved profile, failing if it does not exist.
+    pub fn load(name: &str) -> anyhow::Result<Self> {
+        let f = profile_file(name)?;
+        if !f.exists() {
+            bail!(
+                "Profile {} does not exist, create it with login --profile {}",
+                name,
+                name
+            );
+        }
+        let mut profile: Self = serde_json::from_str(&fs::read_to_string(&f)?)
+            .with_context(|| format!("Failed to parse {}", f.display()))?;
+        profile.name = name.to_string();
+        Ok(profile)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let f = profile_file(&self.name)?;
+        fs::write(f, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Names of the saved profiles, sorted.
+    pub fn list() -> anyhow::Result<Vec<String>> {
+        let mut names: Vec<String> = fs::read_dir(profiles_dir()?)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Options that use this profile: its login, and its endpoint and token unless the options
+    /// set their own.
+    pub fn apply(&self, options: DownloadOptions) -> DownloadOptions {
+        DownloadOptions {
+            endpoint: options.endpoint.or_else(|| self.endpoint.clone()),
+            token: options.token.or_else(|| self.token.clone()),
+            profile: Some(self.name.clone()),
+            ..options
+        }
+    }
+}
+
+/// `~/.modelscope/config/profiles`, created on first use.
+pub(crate) fn profiles_dir() -> anyhow::Result<PathBuf> {
+    let dir = Dirs::config_dir()?.join(PROFILES_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Profile names become file names, so they are kept to a safe set of characters.
+pub(crate) fn check_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid profile name {:?}, use letters, digits, - and _",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn profile_file(name: &str) -> anyhow::Result<PathBuf> {
+    check_name(name)?;
+    Ok(profiles_dir()?.join(format!("{}.json", name)))
+}
@@ -0,0 +1,185 @@
+use crate::ProgressCallback;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Writes the download progress as JSON to a file, for monitoring headless servers with an
+/// external dashboard or `watch cat`.
+///
+/// Progress updates are throttled to one write per interval (1 second by default), completions,
+/// errors and the end of the download are always written. Every write replaces the file
+/// atomically, readers never see a partial file.
+#[derive(Clone)]
+pub struct FileProgressCallback {
+    path: PathBuf,
+    interval: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    report: Report,
+    last_write: Option<Instant>,
+}
+
+#[derive(Default, Serialize)]
+struct Report {
+    /// Unix timestamp in seconds of this write
+    updated_at: u64,
+    downloaded: u64,
+    total: u64,
+    files: BTreeMap<String, FileReport>,
+}
+
+#[derive(Serialize)]
+struct FileReport {
+    downloaded: u64,
+    total: u64,
+    /// `downloading`, `complete`, `skipped` or `error`
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Why the file was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// Retries after errors so far
+    #[serde(skip_serializing_if = "is_zero")]
+    retries: u32,
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+impl FileProgressCallback {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: Duration::from_secs(1),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Minimum time between two writes of progress updates.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn update(&self, force: bool, f: impl FnOnce(&mut Report)) {
+        let mut state = self.state.lock().unwrap();
+        f(&mut state.report);
+        if !force
+            && state
+                .last_write
+                .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return;
+        }
+        state.last_write = Some(Instant::now());
+        state.report.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        // Written under the lock, so concurrent files never interleave their writes
+        if let Err(e) = self.write(&state.report) {
+            tracing::warn!(
+                path = %self.path.display(),
+                error = %e,
+                "failed to write the progress file"
+            );
+        }
+    }
+
+    fn write(&self, report: &Report) -> anyhow::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(report)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProgressCallback for FileProgressCallback {
+    async fn on_file_start(&self, file_name: &str, file_size: u64) {
+        self.update(false, |report| {
+            report.files.insert(
+                file_name.to_string(),
+                FileReport {
+                    downloaded: 0,
+                    total: file_size,
+                    status: "downloading",
+                    error: None,
+                    reason: None,
+                    retries: 0,
+                },
+            );
+        });
+    }
+
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
+        self.update(false, |report| {
+            if let Some(file) = report.files.get_mut(file_name) {
+                file.downloaded = downloaded;
+                file.total = total;
+            }
+        });
+    }
+
+    async fn on_file_complete(&self, file_name: &str) {
+        self.update(true, |report| {
+            if let Some(file) = report.files.get_mut(file_name) {
+                file.downloaded = file.downloaded.max(file.total);
+                file.status = "complete";
+            }
+        });
+    }
+
+    async fn on_file_error(&self, file_name: &str, error: &str) {
+        self.update(true, |report| {
+            if let Some(file) = report.files.get_mut(file_name) {
+                file.status = "error";
+                file.error = Some(error.to_string());
+            }
+        });
+    }
+
+    async fn on_file_skipped(&self, file_name: &str, reason: &str) {
+        self.update(true, |report| {
+            let file = report
+                .files
+                .entry(file_name.to_string())
+                .or_insert(FileReport {
+                    downloaded: 0,
+                    total: 0,
+                    status: "skipped",
+                    error: None,
+                    reason: None,
+                    retries: 0,
+                });
+            file.downloaded = file.total;
+            file.status = "skipped";
+            file.reason = Some(reason.to_string());
+        });
+    }
+
+    async fn on_file_retry(&self, file_name: &str, attempt: u32, _error: &str) {
+        self.update(true, |report| {
+            if let Some(file) = report.files.get_mut(file_name) {
+                file.retries = attempt;
+            }
+        });
+    }
+
+    async fn on_overall_progress(&self, downloaded: u64, total: u64) {
+        self.update(downloaded >= total, |report| {
+            report.downloaded = downloaded;
+            report.total = total;
+        });
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{DownloadSummary, ProgressCallback};
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Prints the download progress to stdout as newline-delimited JSON, one event per line, for
+/// tools that wrap the CLI or a program built on the library.
+///
+/// Every line is an object with an `event` field: `download_start`, `start`, `progress`,
+/// `complete`, `skipped`, `retry`, `error`, `overall` and `download_complete` with the
+/// [`DownloadSummary`]. `progress` and `overall` carry the speed in bytes per second and are
+/// throttled to one line per file per interval (200ms by default), the other events are always
+/// printed. Set [`DownloadOptions::quiet`](crate::DownloadOptions::quiet) so no other output
+/// ends up on stdout.
+#[derive(Clone)]
+pub struct JsonProgressCallback {
+    interval: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    files: HashMap<String, Rate>,
+    overall: Option<Rate>,
+}
+
+/// Speed and throttling of the progress lines of a file or of the whole download.
+struct Rate {
+    started: Instant,
+    /// Bytes at the first update, like a resumed partial file, which don't count for the speed
+    base: Option<u64>,
+    last: Option<Instant>,
+}
+
+impl Rate {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            base: None,
+            last: None,
+        }
+    }
+}
+
+impl Default for JsonProgressCallback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonProgressCallback {
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_millis(200),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Minimum time between two `progress` lines of a file, and two `overall` lines.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn emit(&self, event: serde_json::Value) {
+        // One write per line under the stdout lock, so concurrent files never interleave
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", event);
+        let _ = stdout.flush();
+    }
+
+    /// Bytes per second since the start, or `None` when the line is throttled.
+    fn throttle(&self, rate: &mut Rate, bytes: u64, done: bool) -> Option<u64> {
+        let base = *rate.base.get_or_insert(bytes);
+        if !done && rate.last.is_some_and(|last| last.elapsed() < self.interval) {
+            return None;
+        }
+        rate.last = Some(Instant::now());
+        let secs = rate.started.elapsed().as_secs_f64();
+        Some(if secs > 0.0 {
+            (bytes.saturating_sub(base) as f64 / secs) as u64
+        } else {
+            0
+        })
+    }
+}
+
+#[async_trait]
+impl ProgressCallback for JsonProgressCallback {
+    async fn on_file_start(&self, file_name: &str, file_size: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.files.insert(file_name.to_string(), Rate::new());
+        drop(state);
+        self.emit(json!({ "event": "start", "file": file_name, "total": file_size }));
+    }
+
+    async fn on_file_progress(&self, file_name: &str, downloaded: u64, total: u64) {
+        let mut state = self.state.lock().unwrap();
+        let Some(file) = state.files.get_mut(file_name) else {
+            return;
+        };
+        let Some(speed) = self.throttle(file, downloaded, downloaded >= total && total > 0) else {
+            return;
+        };
+        drop(state);
+        self.emit(json!({
+            "event": "progress",
+            "file": file_name,
+            "downloaded": downloaded,
+            "total": total,
+            "speed": speed,
+        }));
+    }
+
+    async fn on_file_complete(&self, file_name: &str) {
+        self.state.lock().unwrap().files.remove(file_name);
+        self.emit(json!({ "event": "complete", "file": file_name }));
+    }
+
+    async fn on_file_error(&self, file_name: &str, error: &str) {
+        self.state.lock().unwrap().files.remove(file_name);
+        self.emit(json!({ "event": "error", "file": file_name, "error": error }));
+    }
+
+    async fn on_file_skipped(&self, file_name: &str, reason: &str) {
+        self.state.lock().unwrap().files.remove(file_name);
+        self.emit(json!({ "event": "skipped", "file": file_name, "reason": reason }));
+    }
+
+    async fn on_file_retry(&self, file_name: &str, attempt: u32, error: &str) {
+        self.emit(json!({
+            "event": "retry",
+            "file": file_name,
+            "attempt": attempt,
+            "error": error,
+        }));
+    }
+
+    async fn on_overall_progress(&self, downloaded: u64, total: u64) {
+        let mut state = self.state.lock().unwrap();
+        let overall = state.overall.get_or_insert_with(Rate::new);
+        let Some(speed) = self.throttle(overall, downloaded, downloaded >= total) else {
+            return;
+        };
+        drop(state);
+        self.emit(json!({
+            "event": "overall",
+            "downloaded": downloaded,
+            "total": total,
+            "speed": speed,
+        }));
+    }
+
+    async fn on_download_start(&self, total_files: usize, total_bytes: u64) {
+        self.emit(json!({
+            "event": "download_start",
+            "files": total_files,
+            "total": total_bytes,
+        }));
+    }
+
+    async fn on_download_complete(&self, summary: &DownloadSummary) {
+        self.emit(json!({ "event": "download_complete", "summary": summary }));
+    }
+}
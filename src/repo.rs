@@ -0,0 +1,94 @@
+use crate::{DownloadOptions, ModelScope, api};
+use std::str::FromStr;
+
+const CREATE_MODEL_URL: &str = "<endpoint>/api/v1/models";
+/// License of new models when none is given, as the hub names it
+pub const DEFAULT_LICENSE: &str = "Apache License 2.0";
+
+/// Who can see and download a model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    /// Only the owner and the members of its organization
+    #[default]
+    Private,
+    /// Logged in users
+    Internal,
+    /// Anyone
+    Public,
+}
+
+impl FromStr for Visibility {
+    type Err = String;
+
+    /// Parse `private`, `internal` or `public`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "private" => Ok(Self::Private),
+            "internal" => Ok(Self::Internal),
+            "public" => Ok(Self::Public),
+            _ => Err(format!("expected private, internal or public, got {}", s)),
+        }
+    }
+}
+
+impl Visibility {
+    /// The `Visibility` code of the hub API.
+    pub(crate) fn code(self) -> i64 {
+        match self {
+            Self::Private => 1,
+            Self::Internal => 3,
+            Self::Public => 5,
+        }
+    }
+}
+
+impl ModelScope {
+    /// Create an empty model repository, owned by the user or organization the ID starts with,
+    /// and return its URL. Needs a login or token with write access to the owner.
+    pub async fn create_model(
+        model_id: &str,
+        visibility: Visibility,
+        license: &str,
+    ) -> anyhow::Result<String> {
+        Self::create_model_with_options(
+            model_id,
+            visibility,
+            license,
+            None,
+            &DownloadOptions::default(),
+        )
+        .await
+    }
+
+    /// Like [`create_model`](Self::create_model), with the display name shown on the Chinese
+    /// site, the model name when `None`.
+    pub async fn create_model_with_options(
+        model_id: &str,
+        visibility: Visibility,
+        license: &str,
+        chinese_name: Option<&str>,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<String> {
+        options.ensure_modelscope("create a model")?;
+        let Some((owner, name)) = model_id
+            .split_once('/')
+            .filter(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+        else {
+            anyhow::bail!("Invalid model ID {:?}, expected <owner>/<name>", model_id);
+        };
+
+        let client = Self::get_client_with(options).await?;
+        let request = client
+            .post(CREATE_MODEL_URL.replace("<endpoint>", options.endpoint()))
+            .json(&serde_json::json!({
+                "Path": owner,
+                "Name": name,
+                "ChineseName": chinese_name.unwrap_or(name),
+                "Visibility": visibility.code(),
+                "License": license,
+            }));
+        let action = format!("create model {}", model_id);
+        api::send::<serde_json::Value>(request, options, &action).await?;
+        Ok(format!("{}/models/{}", options.endpoint(), model_id))
+    }
+}
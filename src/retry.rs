@@ -0,0 +1,47 @@
+use crate::ModelScopeError;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Whether a failed file download may succeed when tried again: dropped connections, timeouts,
+/// server errors and rate limiting. Disk errors and client errors are not retried.
+pub(crate) fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout()
+                || e.is_connect()
+                || e.is_body()
+                || e.is_request()
+                || crate::is_connection_reset(e);
+        }
+        if let Some(status) = cause.downcast_ref().and_then(ModelScopeError::status) {
+            return status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::TimedOut
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        false
+    })
+}
+
+/// Exponential backoff before retry number `attempt` (from 1), with jitter so that the files
+/// that failed together don't retry in lockstep.
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_DELAY);
+    // Between half and the whole delay, RandomState is seeded randomly per instance
+    let random = RandomState::new().hash_one(attempt);
+    delay.mul_f64(0.5 + (random % 1000) as f64 / 2000.0)
+}
@@ -0,0 +1,85 @@
+use crate::{DownloadOptions, ModelScope, api};
+use serde::{Deserialize, Serialize};
+
+const SEARCH_URL: &str = "<endpoint>/api/v1/dolphin/models";
+
+/// A model found by [`ModelScope::search`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelSummary {
+    pub model_id: String,
+    /// Task tags, like `text-generation`
+    pub tasks: Vec<String>,
+    pub downloads: u64,
+    /// Unix timestamp in seconds of the last update
+    pub last_updated: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SearchData {
+    #[serde(rename = "Model")]
+    model: SearchModels,
+}
+
+#[derive(Deserialize)]
+struct SearchModels {
+    #[serde(rename = "Models", default)]
+    models: Vec<SearchModel>,
+}
+
+#[derive(Deserialize)]
+struct SearchModel {
+    /// Owner of the model
+    #[serde(rename = "Path")]
+    path: String,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Tasks", default)]
+    tasks: Vec<api::Task>,
+    #[serde(rename = "Downloads", default)]
+    downloads: u64,
+    #[serde(rename = "LastUpdatedTime", default)]
+    last_updated: Option<u64>,
+}
+
+impl ModelScope {
+    /// Search the hub for models matching `query`, in the order the hub ranks them.
+    pub async fn search(query: &str, limit: usize) -> anyhow::Result<Vec<ModelSummary>> {
+        Self::search_with_options(query, limit, &DownloadOptions::default()).await
+    }
+
+    /// Like [`search`](Self::search), against the endpoint and with the connection settings of
+    /// the options.
+    pub async fn search_with_options(
+        query: &str,
+        limit: usize,
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Vec<ModelSummary>> {
+        options.ensure_modelscope("search models")?;
+        let client = Self::get_client_with(options).await?;
+        let request = client
+            .put(SEARCH_URL.replace("<endpoint>", options.endpoint()))
+            .json(&serde_json::json!({
+                "Name": query,
+                "PageNumber": 1,
+                "PageSize": limit,
+                "SortBy": "Default",
+                "Target": "",
+                "Criterion": [],
+                "SingleCriterion": [],
+            }));
+        let action = format!("search models for {:?}", query);
+        let data: Option<SearchData> = api::send(request, options, &action).await?;
+
+        let models = data.map(|d| d.model.models).unwrap_or_default();
+        Ok(models
+            .into_iter()
+            .take(limit)
+            .map(|m| ModelSummary {
+                model_id: format!("{}/{}", m.path, m.name),
+                tasks: m.tasks.into_iter().map(|t| t.name).collect(),
+                downloads: m.downloads,
+                last_updated: m.last_updated,
+            })
+            .collect())
+    }
+}
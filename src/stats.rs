@@ -0,0 +1,49 @@
+use crate::RepoFile;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Totals of a repository listing, see [`ModelScope::repo_stats`].
+///
+/// [`ModelScope::repo_stats`]: crate::ModelScope::repo_stats
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepoStats {
+    /// Size of all files in bytes
+    pub total_bytes: u64,
+    pub blob_count: usize,
+    /// Directories, including the ones only implied by file paths
+    pub dir_count: usize,
+    /// Directories above the most deeply nested entry, 0 when everything is at the top level
+    pub max_depth: usize,
+    pub largest_file: Option<RepoFile>,
+}
+
+impl RepoStats {
+    pub fn from_files(files: &[RepoFile]) -> Self {
+        let mut stats = Self::default();
+        let mut dirs = HashSet::new();
+        for file in files {
+            let parts: Vec<&str> = file.path.split('/').filter(|p| !p.is_empty()).collect();
+            // Every proper prefix of the path is a directory
+            for end in 1..parts.len() {
+                dirs.insert(parts[..end].join("/"));
+            }
+            stats.max_depth = stats.max_depth.max(parts.len().saturating_sub(1));
+
+            if file.is_dir() {
+                dirs.insert(parts.join("/"));
+                continue;
+            }
+            stats.blob_count += 1;
+            stats.total_bytes += file.size;
+            if stats
+                .largest_file
+                .as_ref()
+                .is_none_or(|l| file.size > l.size)
+            {
+                stats.largest_file = Some(file.clone());
+            }
+        }
+        stats.dir_count = dirs.len();
+        stats
+    }
+}
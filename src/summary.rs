@@ -0,0 +1,80 @@
+use crate::ChunkHashes;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Result of a download.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DownloadSummary {
+    pub model_id: String,
+    /// Paths of the files that are now complete locally
+    pub downloaded: Vec<String>,
+    /// Files that were left out, with the reason
+    pub skipped: Vec<SkippedFile>,
+    /// Size in bytes of the downloaded files, including the ones that were already complete
+    pub bytes: u64,
+    /// Chunk digests of the downloaded files that were verified, for the manifest
+    #[serde(skip)]
+    pub(crate) chunk_hashes: HashMap<String, ChunkHashes>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+impl DownloadSummary {
+    pub(crate) fn new(model_id: &str) -> Self {
+        Self {
+            model_id: model_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn record(&mut self, path: &str, outcome: FileOutcome) {
+        match outcome {
+            FileOutcome::Downloaded(bytes, chunk_hashes) => {
+                self.downloaded.push(path.to_string());
+                self.bytes += bytes;
+                if let Some(chunk_hashes) = chunk_hashes {
+                    self.chunk_hashes.insert(path.to_string(), chunk_hashes);
+                }
+            }
+            FileOutcome::Skipped(reason) => self.skipped.push(SkippedFile {
+                path: path.to_string(),
+                reason,
+            }),
+        }
+    }
+}
+
+/// What happened to a single file
+#[derive(Debug)]
+pub(crate) enum FileOutcome {
+    /// Complete locally, with the size of the file and its chunk digests if it was verified
+    Downloaded(u64, Option<ChunkHashes>),
+    Skipped(String),
+}
+
+/// A file kept failing until the retry deadline passed, see
+/// [`DownloadOptions::retry_deadline`](crate::DownloadOptions::retry_deadline).
+///
+/// Attached as context to the last error, find it with `error.downcast_ref()`.
+#[derive(Debug, Clone)]
+pub struct RetryDeadlineExceeded {
+    pub path: String,
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for RetryDeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Gave up on {} after {} attempts in {:.1?}, the retry deadline is exhausted",
+            self.path, self.attempts, self.elapsed
+        )
+    }
+}
@@ -0,0 +1,26 @@
+use crate::DownloadSummary;
+use serde::Serialize;
+
+/// What a [`ModelScope::sync`] changed in the local copy of a model, per file.
+///
+/// [`ModelScope::sync`]: crate::ModelScope::sync
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub model_id: String,
+    /// New upstream, downloaded
+    pub added: Vec<String>,
+    /// Changed upstream, downloaded again
+    pub updated: Vec<String>,
+    /// Same sha256 as the local copy, left alone
+    pub unchanged: Vec<String>,
+    /// Removed upstream, deleted locally
+    pub deleted: Vec<String>,
+    /// The download of the added and updated files, and the completion of unchanged ones
+    pub summary: DownloadSummary,
+}
+
+impl SyncReport {
+    pub fn is_up_to_date(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Earliest time the next metadata API call may be sent, shared by all operations
+static NEXT_API_CALL: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Wait until a metadata API call is allowed, keeping at least `min_interval` between calls.
+///
+/// Each caller reserves its own slot before sleeping, so concurrent callers are spaced out
+/// instead of all waking up at the same time.
+pub(crate) async fn throttle_api(min_interval: Option<Duration>) {
+    let Some(min_interval) = min_interval else {
+        return;
+    };
+    let wait = {
+        let mut next = NEXT_API_CALL.lock().unwrap();
+        let now = Instant::now();
+        let slot = next.map_or(now, |n| n.max(now));
+        *next = Some(slot + min_interval);
+        slot - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{DownloadOptions, TlsBackend, pinning};
+use anyhow::{Context, bail};
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::pem::PemObject;
+use std::fs;
+use std::path::Path;
+use std::sync::Once;
+
+static INSECURE_WARNING: Once = Once::new();
+
+/// Apply the TLS settings of the options to a client: the backend, extra root certificates,
+/// certificate pins and skipping verification.
+pub(crate) fn configure(
+    mut client: reqwest::ClientBuilder,
+    options: &DownloadOptions,
+) -> anyhow::Result<reqwest::ClientBuilder> {
+    if options.danger_accept_invalid_certs {
+        if !options.tls_pins.is_empty() {
+            bail!("Certificate pins can't be checked with certificate verification disabled");
+        }
+        INSECURE_WARNING.call_once(|| {
+            tracing::warn!("TLS certificate verification is disabled, do not trust the result")
+        });
+    }
+
+    // Pins need a verifier of our own, which only the rustls backend takes
+    if !options.tls_pins.is_empty() {
+        if options.tls_backend == TlsBackend::Native {
+            bail!("Certificate pinning needs the rustls TLS backend");
+        }
+        let mut roots = vec![];
+        for path in &options.ca_certs {
+            roots.extend(read_roots(path)?);
+        }
+        return Ok(client.tls_backend_preconfigured(pinning::pinned_tls_config(
+            &options.tls_pins,
+            roots,
+            options.http1_only,
+        )?));
+    }
+
+    client = match options.tls_backend {
+        TlsBackend::Rustls => client.tls_backend_rustls(),
+        #[cfg(feature = "native-tls")]
+        TlsBackend::Native => client.tls_backend_native(),
+        #[cfg(not(feature = "native-tls"))]
+        TlsBackend::Native => {
+            bail!("The native TLS backend needs modelscope-ng built with the `native-tls` feature")
+        }
+    };
+    for path in &options.ca_certs {
+        let certs = reqwest::Certificate::from_pem_bundle(&read_pem(path)?)
+            .with_context(|| format!("Invalid certificates in {}", path.display()))?;
+        client = client.tls_certs_merge(certs);
+    }
+    Ok(client.tls_danger_accept_invalid_certs(options.danger_accept_invalid_certs))
+}
+
+/// The certificates of a PEM file, for the rustls config of pinned connections.
+fn read_roots(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_slice_iter(&read_pem(path)?)
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Invalid certificates in {}", path.display()))
+}
+
+fn read_pem(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let pem = fs::read(path)
+        .with_context(|| format!("Failed to read certificates from {}", path.display()))?;
+    if !pem.windows(11).any(|w| w == b"-----BEGIN ") {
+        bail!("No PEM certificates in {}", path.display());
+    }
+    Ok(pem)
+}
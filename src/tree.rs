@@ -0,0 +1,82 @@
+use crate::RepoFile;
+use indicatif::DecimalBytes;
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct Node {
+    /// `Some` for files, `None` for directories
+    size: Option<u64>,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, path: &str, size: Option<u64>) {
+        let mut node = self;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.size = size;
+    }
+
+    fn total_size(&self) -> u64 {
+        self.size.unwrap_or(0) + self.children.values().map(Node::total_size).sum::<u64>()
+    }
+}
+
+/// Render a repository listing like the `tree` command, with file and directory sizes.
+///
+/// Directory entries (`tree` type) are kept so that empty directories are shown too.
+pub fn render_tree(root_name: &str, files: &[RepoFile]) -> String {
+    let mut root = Node::default();
+    for file in files {
+        if file.is_dir() {
+            root.insert(&file.path, None);
+        } else {
+            root.insert(&file.path, Some(file.size));
+        }
+    }
+
+    let mut out = format!("{} ({})\n", root_name, DecimalBytes(root.total_size()));
+    let (mut dirs, mut blobs) = (0, 0);
+    render_children(&root, "", &mut out, &mut dirs, &mut blobs);
+    out.push_str(&format!("\n{} directories, {} files\n", dirs, blobs));
+    out
+}
+
+fn render_children(
+    node: &Node,
+    prefix: &str,
+    out: &mut String,
+    dirs: &mut usize,
+    blobs: &mut usize,
+) {
+    let count = node.children.len();
+    for (index, (name, child)) in node.children.iter().enumerate() {
+        let last = index + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+        match child.size {
+            Some(size) => {
+                *blobs += 1;
+                out.push_str(&format!(
+                    "{}{}{} ({})\n",
+                    prefix,
+                    branch,
+                    name,
+                    DecimalBytes(size)
+                ));
+            }
+            None => {
+                *dirs += 1;
+                out.push_str(&format!(
+                    "{}{}{}/ ({})\n",
+                    prefix,
+                    branch,
+                    name,
+                    DecimalBytes(child.total_size())
+                ));
+                let next = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                render_children(child, &next, out, dirs, blobs);
+            }
+        }
+    }
+}
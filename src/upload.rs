@@ -0,0 +1,463 @@
+use crate::verify::to_hex;
+use crate::{
+    DownloadOptions, LOCK_FILE, MANIFEST_FILE, ModelScope, ModelScopeError, ProgressCallback, api,
+    retry,
+};
+use anyhow::Context;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+const LFS_BATCH_URL: &str = "<endpoint>/api/v1/repos/<kind>s/<model_id>/info/lfs/objects/batch";
+const COMMIT_URL: &str = "<endpoint>/api/v1/repos/<kind>s/<model_id>/commit/<revision>";
+
+/// What [`ModelScope::upload`] sent to the hub.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UploadSummary {
+    pub model_id: String,
+    /// Repository paths of the files whose content was uploaded
+    pub uploaded: Vec<String>,
+    /// Repository paths of the files the hub already had the content of, e.g. from an earlier
+    /// interrupted upload, or that have the same content as an uploaded file. They are only
+    /// committed
+    pub existing: Vec<String>,
+    /// Bytes sent
+    pub bytes: u64,
+}
+
+/// A local file to upload.
+struct LocalFile {
+    path: PathBuf,
+    /// Path in the repository, with `/` separators
+    repo_path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    operation: &'static str,
+    transfers: [&'static str; 2],
+    objects: Vec<LfsObject<'a>>,
+}
+
+#[derive(Serialize)]
+struct LfsObject<'a> {
+    oid: &'a str,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    objects: Vec<BatchObject>,
+}
+
+#[derive(Deserialize)]
+struct BatchObject {
+    oid: String,
+    #[serde(default)]
+    actions: Option<BatchActions>,
+    #[serde(default)]
+    error: Option<BatchError>,
+}
+
+#[derive(Deserialize)]
+struct BatchActions {
+    upload: Option<BatchAction>,
+    verify: Option<BatchAction>,
+}
+
+#[derive(Clone, Deserialize)]
+struct BatchAction {
+    href: String,
+    #[serde(default)]
+    header: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct BatchError {
+    code: i64,
+    message: String,
+}
+
+impl ModelScope {
+    /// Upload the files of a local directory to an existing repository and commit them to the
+    /// revision of the options, using the saved login or token.
+    ///
+    /// Files whose content the hub already has are only committed, so an interrupted upload
+    /// picks up where it stopped. When the hub asks for a large file in parts, each part is
+    /// retried on its own after a network error. The download manifest, lockfile, partial
+    /// downloads and `.git` are not uploaded.
+    pub async fn upload<C: ProgressCallback + Clone + 'static>(
+        model_id: &str,
+        local_dir: impl AsRef<Path>,
+        commit_message: &str,
+        options: &DownloadOptions,
+        callback: C,
+    ) -> anyhow::Result<UploadSummary> {
+        options.ensure_modelscope("upload")?;
+        let local_dir = local_dir.as_ref();
+        if !local_dir.is_dir() {
+            anyhow::bail!("{} is not a directory", local_dir.display());
+        }
+
+        let mut paths = vec![];
+        collect_files(local_dir, local_dir, &mut paths)?;
+        if paths.is_empty() {
+            anyhow::bail!("No files to upload in {}", local_dir.display());
+        }
+        paths.sort();
+        let files: Vec<LocalFile> = futures_util::stream::iter(paths)
+            .map(|(path, repo_path)| async move {
+                let (size, sha256) = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || hash_file(&path)
+                })
+                .await??;
+                anyhow::Ok(LocalFile {
+                    path,
+                    repo_path,
+                    size,
+                    sha256,
+                })
+            })
+            .buffered(options.verify_concurrency())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<_>>()?;
+
+        let client = Self::get_client_with(options).await?;
+        let url = |template: &str| {
+            template
+                .replace("<endpoint>", options.endpoint())
+                .replace("<kind>", &options.repo_type.to_string())
+                .replace("<model_id>", model_id)
+                .replace("<revision>", options.revision())
+        };
+
+        // The hub answers which contents it is missing
+        let batch = BatchRequest {
+            operation: "upload",
+            transfers: ["basic", "multipart"],
+            objects: files
+                .iter()
+                .map(|f| LfsObject {
+                    oid: &f.sha256,
+                    size: f.size,
+                })
+                .collect(),
+        };
+        let resp = client
+            .post(url(LFS_BATCH_URL))
+            .header(reqwest::header::ACCEPT, "application/vnd.git-lfs+json")
+            .json(&batch)
+            .send()
+            .await
+            .with_context(|| format!("Failed to prepare the upload to {}", model_id))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            return Err(
+                anyhow::Error::new(ModelScopeError::from_status(status)).context(format!(
+                    "Failed to prepare the upload to {}: HTTP {} {}\nTip: The repository has to exist and you need write access",
+                    model_id,
+                    status,
+                    resp.text().await?
+                )),
+            );
+        }
+        let batch: BatchResponse = resp
+            .json()
+            .await
+            .with_context(|| format!("Failed to prepare the upload to {}", model_id))?;
+        let mut actions = HashMap::new();
+        for object in batch.objects {
+            if let Some(error) = object.error {
+                let error = ModelScopeError::Api {
+                    code: error.code,
+                    message: error.message,
+                };
+                return Err(anyhow::Error::new(error)
+                    .context(format!("Failed to prepare the upload to {}", model_id)));
+            }
+            actions.insert(object.oid, object.actions);
+        }
+
+        let mut summary = UploadSummary {
+            model_id: model_id.to_string(),
+            ..Default::default()
+        };
+        let mut pending = vec![];
+        // Files with the same content share one upload
+        let mut queued = HashSet::new();
+        for file in &files {
+            match actions.get(&file.sha256) {
+                Some(Some(BatchActions {
+                    upload: Some(upload),
+                    verify,
+                })) if queued.insert(&file.sha256) => pending.push((file, upload, verify)),
+                _ => summary.existing.push(file.repo_path.clone()),
+            }
+        }
+
+        let client = &client;
+        let uploads = pending.into_iter().map(|(file, upload, verify)| {
+            let callback = callback.clone();
+            async move {
+                callback.on_file_start(&file.repo_path, file.size).await;
+                let result = async {
+                    Self::upload_content(client, file, upload, options, &callback).await?;
+                    if let Some(verify) = verify {
+                        let mut request = client.post(&verify.href).json(&LfsObject {
+                            oid: &file.sha256,
+                            size: file.size,
+                        });
+                        for (name, value) in &verify.header {
+                            request = request.header(name, value);
+                        }
+                        check_status(request.send().await?)?;
+                    }
+                    anyhow::Ok(())
+                }
+                .await;
+                match &result {
+                    Ok(()) => callback.on_file_complete(&file.repo_path).await,
+                    Err(e) => {
+                        callback
+                            .on_file_error(&file.repo_path, &e.to_string())
+                            .await
+                    }
+                }
+                result
+                    .with_context(|| format!("Failed to upload {}", file.path.display()))
+                    .map(|()| file)
+            }
+        });
+        let mut uploads =
+            futures_util::stream::iter(uploads).buffer_unordered(options.max_open_files());
+        while let Some(file) = uploads.next().await {
+            let file = file?;
+            summary.uploaded.push(file.repo_path.clone());
+            summary.bytes += file.size;
+        }
+
+        let commit = serde_json::json!({
+            "commit_message": commit_message,
+            "actions": files
+                .iter()
+                .map(|f| serde_json::json!({
+                    "action": "create",
+                    "path": f.repo_path,
+                    "type": "lfs",
+                    "size": f.size,
+                    "sha256": f.sha256,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        let action = format!("commit the upload to {}", model_id);
+        api::send::<serde_json::Value>(
+            client.post(url(COMMIT_URL)).json(&commit),
+            options,
+            &action,
+        )
+        .await?;
+        Ok(summary)
+    }
+
+    /// Send the content of a file, whole or in the parts the hub asked for.
+    async fn upload_content<C: ProgressCallback + Clone + 'static>(
+        client: &reqwest::Client,
+        file: &LocalFile,
+        upload: &BatchAction,
+        options: &DownloadOptions,
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        let sent = Arc::new(AtomicU64::new(0));
+        let chunk_size = upload
+            .header
+            .get("chunk_size")
+            .and_then(|size| size.parse::<u64>().ok())
+            .filter(|size| *size > 0);
+        let Some(chunk_size) = chunk_size else {
+            let part = Part {
+                url: &upload.href,
+                headers: &upload.header,
+                range: 0..file.size,
+            };
+            Self::put_part(client, file, part, &sent, options, callback).await?;
+            return Ok(());
+        };
+
+        // Multipart, the part URLs are the numbered headers
+        let mut part_urls: Vec<(u64, &String)> = upload
+            .header
+            .iter()
+            .filter_map(|(name, url)| Some((name.parse().ok()?, url)))
+            .filter(|(number, _)| *number > 0)
+            .collect();
+        part_urls.sort();
+        let no_headers = HashMap::new();
+        let mut parts = vec![];
+        for (number, url) in part_urls {
+            let start = (number - 1) * chunk_size;
+            let part = Part {
+                url,
+                headers: &no_headers,
+                range: start.min(file.size)..(start + chunk_size).min(file.size),
+            };
+            let etag = Self::put_part(client, file, part, &sent, options, callback).await?;
+            parts.push(serde_json::json!({ "partNumber": number, "etag": etag }));
+        }
+        let completion = serde_json::json!({ "oid": file.sha256, "parts": parts });
+        check_status(client.post(&upload.href).json(&completion).send().await?)?;
+        Ok(())
+    }
+
+    /// PUT a byte range of a file, tried again after transient errors, and return the `ETag`
+    /// of the response. `sent` counts the bytes of the file sent so far, for the progress.
+    async fn put_part<C: ProgressCallback + Clone + 'static>(
+        client: &reqwest::Client,
+        file: &LocalFile,
+        part: Part<'_>,
+        sent: &Arc<AtomicU64>,
+        options: &DownloadOptions,
+        callback: &C,
+    ) -> anyhow::Result<String> {
+        let Part {
+            url,
+            headers,
+            range,
+        } = part;
+        let len = range.end - range.start;
+        let before = sent.load(Ordering::Relaxed);
+        let mut attempt = 0;
+        loop {
+            let result = async {
+                let mut local = tokio::fs::File::open(&file.path).await?;
+                local.seek(SeekFrom::Start(range.start)).await?;
+                let (callback, sent) = (callback.clone(), sent.clone());
+                let (repo_path, size) = (file.repo_path.clone(), file.size);
+                let body = tokio_util::io::ReaderStream::new(local.take(len)).then(move |chunk| {
+                    let (callback, sent, repo_path) =
+                        (callback.clone(), sent.clone(), repo_path.clone());
+                    async move {
+                        if let Ok(chunk) = &chunk {
+                            let total = sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                            callback
+                                .on_file_progress(&repo_path, total + chunk.len() as u64, size)
+                                .await;
+                        }
+                        chunk
+                    }
+                });
+                let mut request = client
+                    .put(url)
+                    .header(reqwest::header::CONTENT_LENGTH, len)
+                    .body(reqwest::Body::wrap_stream(body));
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                let resp = check_status(options.until_cancelled(request.send()).await??)?;
+                let etag = resp
+                    .headers()
+                    .get(reqwest::header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                anyhow::Ok(etag)
+            }
+            .await;
+            match result {
+                Err(e) if attempt < options.network_retries && retry::is_transient(&e) => {
+                    attempt += 1;
+                    sent.store(before, Ordering::Relaxed);
+                    callback
+                        .on_file_retry(&file.repo_path, attempt, &format!("{:#}", e))
+                        .await;
+                    tracing::warn!(
+                        path = file.repo_path,
+                        attempt,
+                        retries = options.network_retries,
+                        error = format!("{:#}", e),
+                        "upload failed, retrying"
+                    );
+                    tokio::time::sleep(retry::backoff(attempt)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A byte range of a file and where to PUT it.
+struct Part<'a> {
+    url: &'a str,
+    headers: &'a HashMap<String, String>,
+    range: Range<u64>,
+}
+
+fn check_status(resp: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    if !resp.status().is_success() {
+        return Err(ModelScopeError::from_status(resp.status()).into());
+    }
+    Ok(resp)
+}
+
+/// Files under `dir`, with their repository paths relative to `root`.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(PathBuf, String)>,
+) -> anyhow::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        // Download bookkeeping, not repository content
+        if name == ".git"
+            || name == MANIFEST_FILE
+            || name == LOCK_FILE
+            || name.ends_with(".incomplete")
+            || name.ends_with(".incomplete.json")
+        {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root)?;
+            let repo_path = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((path, repo_path));
+        }
+    }
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> anyhow::Result<(u64, String)> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+    let mut size = 0;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, to_hex(&hasher.finalize())))
+}
@@ -0,0 +1,57 @@
+use crate::{DownloadOptions, ModelScope, ModelScopeError, api};
+use serde::{Deserialize, Serialize};
+
+const USER_INFO_URL: &str = "<endpoint>/api/v1/users/login/info";
+
+/// The account the stored credentials belong to, see [`ModelScope::current_user`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserInfo {
+    #[serde(rename = "Username")]
+    pub username: String,
+    #[serde(rename = "Email", default)]
+    pub email: Option<String>,
+    /// Organizations the user is a member of
+    #[serde(rename = "Organizations", default, deserialize_with = "org_names")]
+    pub organizations: Vec<String>,
+}
+
+fn org_names<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    struct Org {
+        #[serde(rename = "Name")]
+        name: String,
+    }
+    let orgs: Option<Vec<Org>> = Option::deserialize(deserializer)?;
+    Ok(orgs.into_iter().flatten().map(|o| o.name).collect())
+}
+
+impl ModelScope {
+    /// The user the stored login or the token belongs to, `None` when not logged in or the
+    /// credentials expired.
+    pub async fn current_user() -> anyhow::Result<Option<UserInfo>> {
+        Self::current_user_with_options(&DownloadOptions::default()).await
+    }
+
+    pub async fn current_user_with_options(
+        options: &DownloadOptions,
+    ) -> anyhow::Result<Option<UserInfo>> {
+        options.ensure_modelscope("get the logged in user")?;
+        let has_token = options.token.is_some() || crate::env_token().is_some();
+        if !has_token && Self::get_cookies(options.profile.as_deref())?.is_none() {
+            return Ok(None);
+        }
+        let client = Self::get_client_with(options).await?;
+        let request = client.get(USER_INFO_URL.replace("<endpoint>", options.endpoint()));
+        match api::send(request, options, "get the logged in user").await {
+            Err(e)
+                if matches!(
+                    ModelScopeError::classify(&e),
+                    Some(ModelScopeError::Auth { .. })
+                ) =>
+            {
+                Ok(None)
+            }
+            result => result,
+        }
+    }
+}
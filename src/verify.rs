@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::ops::Range;
+use std::path::Path;
+
+/// Per-chunk sha256 digests of a file, recorded in the manifest when a download is verified.
+///
+/// Chunk `i` covers bytes `i * chunk_size .. (i + 1) * chunk_size`, the last chunk may be shorter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkHashes {
+    pub chunk_size: u64,
+    pub sha256: Vec<String>,
+}
+
+/// Size of the blocks a verified download is hashed in, when the hub has no chunk digests
+const BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Downloaded content does not match its expected hash.
+#[derive(Debug, Clone)]
+pub struct ChecksumMismatch {
+    pub path: String,
+    pub message: String,
+    /// Byte ranges known to be corrupt, empty when only the whole-file digest was checked
+    pub ranges: Vec<Range<u64>>,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Checksum mismatch for {}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+/// Incrementally verifies a file while it is being written.
+///
+/// Chunk hashes, when available, are checked as soon as each chunk is complete so corruption is
+/// reported without waiting for the whole file. The whole-file sha256 is checked in [`finish`].
+/// Without chunk hashes the content is still hashed in blocks of [`BLOCK_SIZE`], which
+/// [`finish`] returns once the file is verified, for `repair` to find the corrupt blocks later.
+///
+/// [`finish`]: StreamVerifier::finish
+pub(crate) struct StreamVerifier {
+    path: String,
+    expected_sha256: String,
+    whole: Sha256,
+    chunks: ChunkState,
+}
+
+struct ChunkState {
+    chunk_size: u64,
+    /// Empty when there is nothing to check the chunks against
+    expected: Vec<String>,
+    actual: Vec<String>,
+    hasher: Sha256,
+    filled: u64,
+}
+
+impl StreamVerifier {
+    pub(crate) fn new(path: &str, expected_sha256: &str, chunks: Option<&ChunkHashes>) -> Self {
+        let chunks = chunks.filter(|c| c.chunk_size > 0 && !c.sha256.is_empty());
+        Self {
+            path: path.to_string(),
+            expected_sha256: expected_sha256.to_lowercase(),
+            whole: Sha256::new(),
+            chunks: ChunkState {
+                chunk_size: chunks.map_or(BLOCK_SIZE, |c| c.chunk_size),
+                expected: chunks.map(|c| c.sha256.clone()).unwrap_or_default(),
+                actual: vec![],
+                hasher: Sha256::new(),
+                filled: 0,
+            },
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) -> anyhow::Result<()> {
+        self.whole.update(data);
+
+        let state = &mut self.chunks;
+        while !data.is_empty() {
+            let room = (state.chunk_size - state.filled) as usize;
+            let take = room.min(data.len());
+            state.hasher.update(&data[..take]);
+            state.filled += take as u64;
+            data = &data[take..];
+            if state.filled == state.chunk_size {
+                Self::check_chunk(&self.path, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the trailing partial chunk and the whole-file digest. Returns the chunk digests of
+    /// the file when it matched its sha256, `None` when there was no sha256 to check.
+    pub(crate) fn finish(mut self) -> anyhow::Result<Option<ChunkHashes>> {
+        if self.chunks.filled > 0 {
+            Self::check_chunk(&self.path, &mut self.chunks)?;
+        }
+
+        if self.expected_sha256.is_empty() {
+            return Ok(None);
+        }
+        let actual = to_hex(&self.whole.finalize());
+        if actual != self.expected_sha256 {
+            return Err(ChecksumMismatch {
+                path: self.path,
+                message: format!("expected sha256 {}, got {}", self.expected_sha256, actual),
+                ranges: vec![],
+            }
+            .into());
+        }
+        Ok(Some(ChunkHashes {
+            chunk_size: self.chunks.chunk_size,
+            sha256: self.chunks.actual,
+        }))
+    }
+
+    fn check_chunk(path: &str, state: &mut ChunkState) -> anyhow::Result<()> {
+        let actual = to_hex(&std::mem::take(&mut state.hasher).finalize());
+        let index = state.actual.len();
+        let start = index as u64 * state.chunk_size;
+        match state.expected.get(index) {
+            Some(expected) if !expected.eq_ignore_ascii_case(&actual) => {
+                return Err(ChecksumMismatch {
+                    path: path.to_string(),
+                    message: format!(
+                        "chunk {} (bytes {}-{}) does not match",
+                        index,
+                        start,
+                        start + state.filled - 1
+                    ),
+                    ranges: std::iter::once(start..start + state.filled).collect(),
+                }
+                .into());
+            }
+            _ => {}
+        }
+        state.actual.push(actual);
+        state.filled = 0;
+        Ok(())
+    }
+}
+
+/// Feed the first `len` bytes of a local file into a verifier.
+pub(crate) fn update_from_file(
+    verifier: &mut StreamVerifier,
+    file_path: &Path,
+    len: u64,
+) -> anyhow::Result<()> {
+    let mut file = fs::File::open(file_path)?.take(len);
+    let mut buf = vec![0; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        verifier.update(&buf[..n])?;
+    }
+    Ok(())
+}
+
+/// Hash a local file chunk by chunk and return the byte ranges that do not match, adjacent bad
+/// chunks merged. Chunks past the end of the file are reported as missing too.
+pub(crate) fn corrupt_ranges(
+    file_path: &Path,
+    chunks: &ChunkHashes,
+    size: u64,
+) -> anyhow::Result<Vec<Range<u64>>> {
+    let mut ranges: Vec<Range<u64>> = vec![];
+    if chunks.chunk_size == 0 {
+        return Ok(ranges);
+    }
+    let mut file = fs::File::open(file_path)?;
+    let mut buf = vec![0; chunks.chunk_size as usize];
+    for (index, expected) in chunks.sha256.iter().enumerate() {
+        let start = index as u64 * chunks.chunk_size;
+        if start >= size {
+            break;
+        }
+        let end = (start + chunks.chunk_size).min(size);
+        let len = (end - start) as usize;
+        let read = read_full(&mut file, &mut buf[..len])?;
+        let good =
+            read == len && to_hex(&Sha256::digest(&buf[..len])).eq_ignore_ascii_case(expected);
+        if good {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    Ok(ranges)
+}
+
+/// Length of the leading whole chunks of the first `len` bytes of a local file that match their
+/// digests, the part of a partial file known to belong to this version of the file.
+pub(crate) fn matching_prefix(
+    file_path: &Path,
+    chunks: &ChunkHashes,
+    len: u64,
+) -> anyhow::Result<u64> {
+    if chunks.chunk_size == 0 {
+        return Ok(0);
+    }
+    let whole = (len / chunks.chunk_size).min(chunks.sha256.len() as u64) * chunks.chunk_size;
+    let bad = corrupt_ranges(file_path, chunks, whole)?;
+    Ok(bad.first().map_or(whole, |r| r.start))
+}
+
+fn read_full(file: &mut fs::File, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_of_a_verified_file_locate_corruption() {
+        let mut data = vec![7u8; BLOCK_SIZE as usize + 10];
+        let sha256 = to_hex(&Sha256::digest(&data));
+        let mut verifier = StreamVerifier::new("model.bin", &sha256, None);
+        verifier.update(&data).unwrap();
+        let blocks = verifier.finish().unwrap().unwrap();
+        assert_eq!(blocks.chunk_size, BLOCK_SIZE);
+        assert_eq!(blocks.sha256.len(), 2);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        data[BLOCK_SIZE as usize + 3] = 0;
+        fs::write(&path, &data).unwrap();
+        let ranges = corrupt_ranges(&path, &blocks, data.len() as u64).unwrap();
+        assert_eq!(ranges, vec![BLOCK_SIZE..BLOCK_SIZE + 10]);
+
+        // Downloading it again, the bad block is reported before the whole-file digest
+        let mut verifier = StreamVerifier::new("model.bin", &sha256, Some(&blocks));
+        verifier.update(&data).unwrap();
+        let err = verifier.finish().unwrap_err();
+        let mismatch = err.downcast_ref::<ChecksumMismatch>().unwrap();
+        assert_eq!(mismatch.ranges, vec![BLOCK_SIZE..BLOCK_SIZE + 10]);
+    }
+
+    #[test]
+    fn unverified_files_have_no_blocks() {
+        let mut verifier = StreamVerifier::new("model.bin", "", None);
+        verifier.update(b"data").unwrap();
+        assert!(verifier.finish().unwrap().is_none());
+    }
+
+    #[test]
+    fn matching_prefix_stops_at_the_first_bad_chunk() {
+        let chunk = |b: u8| vec![b; 4];
+        let chunks = ChunkHashes {
+            chunk_size: 4,
+            sha256: [1, 2, 3]
+                .map(|b| to_hex(&Sha256::digest(chunk(b))))
+                .to_vec(),
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+
+        // The trailing partial chunk can't be checked
+        fs::write(&path, [chunk(1), chunk(2), vec![3; 2]].concat()).unwrap();
+        assert_eq!(matching_prefix(&path, &chunks, 10).unwrap(), 8);
+        // Another version of the file
+        fs::write(&path, [chunk(1), chunk(9), vec![3; 2]].concat()).unwrap();
+        assert_eq!(matching_prefix(&path, &chunks, 10).unwrap(), 4);
+        fs::write(&path, [chunk(9), chunk(2)].concat()).unwrap();
+        assert_eq!(matching_prefix(&path, &chunks, 8).unwrap(), 0);
+    }
+}
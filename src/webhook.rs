@@ -0,0 +1,78 @@
+use crate::DownloadSummary;
+use serde_json::{Map, Value, json};
+use std::time::Duration;
+
+/// A URL that receives a JSON summary once a download finished, successfully or not.
+///
+/// The payload has `status` (`success` or `failure`), `model_id`, `bytes`, `duration_secs`,
+/// `files`, `skipped` and, on failure, `error`, merged with `fields`.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    /// Extra fields added to every payload, e.g. a job id. They override the built-in fields.
+    pub fields: Map<String, Value>,
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            fields: Map::new(),
+        }
+    }
+
+    fn payload(
+        &self,
+        model_id: &str,
+        result: &anyhow::Result<DownloadSummary>,
+        duration: Duration,
+    ) -> Value {
+        let mut payload = match result {
+            Ok(summary) => json!({
+                "status": "success",
+                "model_id": model_id,
+                "bytes": summary.bytes,
+                "duration_secs": duration.as_secs_f64(),
+                "files": summary.downloaded.len(),
+                "skipped": summary.skipped.len(),
+            }),
+            Err(e) => json!({
+                "status": "failure",
+                "model_id": model_id,
+                "bytes": 0,
+                "duration_secs": duration.as_secs_f64(),
+                "files": 0,
+                "skipped": 0,
+                "error": format!("{:#}", e),
+            }),
+        };
+        if let Some(object) = payload.as_object_mut() {
+            object.extend(self.fields.clone());
+        }
+        payload
+    }
+
+    /// POST the summary, a failing webhook only prints a warning and never fails the download.
+    pub(crate) async fn notify(
+        &self,
+        client: &reqwest::Client,
+        model_id: &str,
+        result: &anyhow::Result<DownloadSummary>,
+        duration: Duration,
+    ) {
+        let payload = self.payload(model_id, result, duration);
+        let res = client
+            .post(&self.url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = res {
+            tracing::warn!(
+                url = crate::redact_url(&self.url),
+                error = %e.without_url(),
+                "failed to notify the webhook"
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::RepoFile;
+
+/// Extensions of model weight files, see [`is_weight_file`]
+pub const WEIGHT_EXTENSIONS: [&str; 11] = [
+    "safetensors",
+    "bin",
+    "pt",
+    "pth",
+    "ckpt",
+    "gguf",
+    "onnx",
+    "h5",
+    "msgpack",
+    "pdparams",
+    "npz",
+];
+
+/// Whether a repository path looks like model weights rather than a config or tokenizer file.
+pub fn is_weight_file(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, ext)| {
+        WEIGHT_EXTENSIONS
+            .iter()
+            .any(|w| w.eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Keep a single weight format: when the repository has `.safetensors` files, drop the `.bin`
+/// weights and their `.bin.index.json`, otherwise keep the `.bin` files as they are.
+pub(crate) fn prefer_safetensors(files: &mut Vec<RepoFile>) {
+    let lower = |f: &RepoFile| f.path.to_lowercase();
+    let has_safetensors = files
+        .iter()
+        .any(|f| !f.is_dir() && lower(f).ends_with(".safetensors"));
+    if has_safetensors {
+        files.retain(|f| {
+            let path = lower(f);
+            f.is_dir() || !(path.ends_with(".bin") || path.ends_with(".bin.index.json"))
+        });
+    }
+}